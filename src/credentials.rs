@@ -0,0 +1,193 @@
+use std::{io, os::unix::process::CommandExt, process::Command};
+
+use libc::{gid_t, uid_t};
+
+use crate::platform::sys::cvt;
+
+/// The user, group and supplementary groups a process runs as, see `SpawnOptions::credentials`.
+///
+/// The process also loses every capability, on Linux, so that a process spawned by root does not keep any privilege,
+/// even when it runs as root.
+/// Only the identity changes, the environment of the process, such as `HOME` or `USER`, is the one of the command.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use proc_guard::{Credentials, ProcGuard, ProcessTermination, SpawnOptions};
+///
+/// // Run as nobody:nogroup without supplementary groups
+/// let guard = ProcGuard::spawn_with(
+///     Command::new("sleep").arg("1"),
+///     ProcessTermination::KillWait,
+///     SpawnOptions::new().credentials(Credentials::new(65534, 65534)),
+/// )?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    uid: u32,
+    gid: u32,
+    groups: Vec<u32>,
+}
+
+impl Credentials {
+    /// Creates the credentials of a process running as `uid` and `gid`, without supplementary groups.
+    pub fn new(uid: u32, gid: u32) -> Self {
+        Credentials {
+            uid,
+            gid,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Sets the supplementary groups of the process.
+    pub fn groups(mut self, groups: impl IntoIterator<Item = u32>) -> Self {
+        self.groups = groups.into_iter().collect();
+        self
+    }
+
+    /// Switches the process spawned by `command` to the credentials before it runs the command.
+    ///
+    /// The supplementary groups are only set if they differ from the ones of the current process, and the bounding
+    /// set is only cleared if the current process has `CAP_SETPCAP`, so that an unprivileged process can spawn the
+    /// process as itself. The closure must run after the ones that need privileges.
+    pub(crate) fn configure(&self, command: &mut Command) -> io::Result<()> {
+        // Nothing can be allocated between the fork and the exec
+        let uid = self.uid as uid_t;
+        let gid = self.gid as gid_t;
+        let mut groups: Vec<gid_t> = self.groups.iter().map(|&group| group as gid_t).collect();
+        groups.sort_unstable();
+        groups.dedup();
+        let set_groups = groups != current_groups()?;
+        #[cfg(target_os = "linux")]
+        let drop_bounding = has_effective_capability(CAP_SETPCAP)?;
+
+        unsafe {
+            command.pre_exec(move || {
+                // Dropping capabilities from the bounding set requires `CAP_SETPCAP`, which `setresuid` drops
+                #[cfg(target_os = "linux")]
+                if drop_bounding {
+                    drop_bounding_set()?;
+                }
+
+                if set_groups {
+                    cvt(libc::setgroups(groups.len() as _, groups.as_ptr()))?;
+                }
+                cvt(libc::setresgid(gid, gid, gid))?;
+                cvt(libc::setresuid(uid, uid, uid))?;
+
+                #[cfg(target_os = "linux")]
+                drop_capabilities()?;
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns the sorted supplementary groups of the current process, without duplicates.
+fn current_groups() -> io::Result<Vec<gid_t>> {
+    let count = cvt(unsafe { libc::getgroups(0, std::ptr::null_mut()) })?;
+    let mut groups = vec![0; count as usize];
+    let count = cvt(unsafe { libc::getgroups(count, groups.as_mut_ptr()) })?;
+    groups.truncate(count as usize);
+    groups.sort_unstable();
+    groups.dedup();
+    Ok(groups)
+}
+
+/// Removes every capability from the bounding set of the current process, so that no executable can grant them.
+#[cfg(target_os = "linux")]
+fn drop_bounding_set() -> io::Result<()> {
+    // The capabilities beyond the last one of the kernel are invalid
+    for capability in 0..64 {
+        if unsafe { libc::prctl(libc::PR_CAPBSET_DROP, capability) } == -1 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::EINVAL) {
+                return Err(error);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The capability allowing to remove capabilities from the bounding set.
+#[cfg(target_os = "linux")]
+const CAP_SETPCAP: u32 = 8;
+
+/// `_LINUX_CAPABILITY_VERSION_3`, whose capability sets are split in two halves.
+#[cfg(target_os = "linux")]
+const CAPABILITY_VERSION: u32 = 0x2008_0522;
+
+/// The header of `capget` and `capset`.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct CapabilityHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+/// A half of the capability sets of `capget` and `capset`, the 64 capabilities being split in two.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CapabilityData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+/// Returns whether the current thread has `capability` in its effective set.
+#[cfg(target_os = "linux")]
+fn has_effective_capability(capability: u32) -> io::Result<bool> {
+    let mut header = CapabilityHeader {
+        version: CAPABILITY_VERSION,
+        pid: 0,
+    };
+    let mut data = [CapabilityData {
+        effective: 0,
+        permitted: 0,
+        inheritable: 0,
+    }; 2];
+    let get = unsafe { libc::syscall(libc::SYS_capget, &mut header, data.as_mut_ptr()) };
+    cvt(get as libc::c_int)?;
+    let half = data[(capability / 32) as usize];
+    Ok(half.effective & (1 << (capability % 32)) != 0)
+}
+
+/// Clears the effective, permitted, inheritable and ambient capabilities of the current process, which a process
+/// running as root keeps after `setresuid`.
+#[cfg(target_os = "linux")]
+fn drop_capabilities() -> io::Result<()> {
+    // Ambient capabilities exist since Linux 4.3
+    let cleared = unsafe {
+        libc::prctl(
+            libc::PR_CAP_AMBIENT,
+            libc::PR_CAP_AMBIENT_CLEAR_ALL,
+            0,
+            0,
+            0,
+        )
+    };
+    if cleared == -1 && io::Error::last_os_error().raw_os_error() != Some(libc::EINVAL) {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut header = CapabilityHeader {
+        version: CAPABILITY_VERSION,
+        pid: 0,
+    };
+    let data = [CapabilityData {
+        effective: 0,
+        permitted: 0,
+        inheritable: 0,
+    }; 2];
+    let set = unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) };
+    cvt(set as libc::c_int)?;
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    process::{Child, ExitStatus},
+    time::{Duration, Instant},
+};
+
+use libc::{c_int, kill, pid_t, SIGQUIT};
+
+use crate::{
+    error::Error,
+    output::{OutputPump, OutputStream},
+};
+
+/// How long a dump step waits for the process to write its diagnostics by default.
+const DEFAULT_DUMP_WINDOW: Duration = Duration::from_secs(2);
+
+/// A request for diagnostics sent to a process before it is killed, such as the thread dump a JVM prints
+/// or the goroutine dump a Go program prints when it receives `SIGQUIT`.
+///
+/// The step sends the signal, then waits for the capture window to elapse or the process to exit while recording
+/// one stream of the piped output and, optionally, what is appended to a dump file.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::time::Duration;
+/// use proc_guard::{DiagnosticDump, OutputStream, TerminationPlan};
+///
+/// // A JVM prints its thread dump on its stdout
+/// let plan = TerminationPlan::new()
+///     .ctrl_c()
+///     .wait_timeout(Duration::from_secs(10))
+///     .dump(DiagnosticDump::new().stream(OutputStream::Stdout))
+///     .kill()
+///     .wait();
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiagnosticDump {
+    signal: c_int,
+    window: Duration,
+    stream: OutputStream,
+    file: Option<PathBuf>,
+}
+
+impl DiagnosticDump {
+    /// Creates a dump sending `SIGQUIT` and recording the stderr of the process for 2 seconds.
+    pub fn new() -> Self {
+        DiagnosticDump {
+            signal: SIGQUIT,
+            window: DEFAULT_DUMP_WINDOW,
+            stream: OutputStream::Stderr,
+            file: None,
+        }
+    }
+
+    /// Sets the signal asking the process for diagnostics.
+    pub fn signal(mut self, signal: c_int) -> Self {
+        self.signal = signal;
+        self
+    }
+
+    /// Sets how long to wait for the process to write its diagnostics.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the stream of the piped output recorded during the capture window.
+    pub fn stream(mut self, stream: OutputStream) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// Sets a file the process writes its diagnostics to, what is appended to it during the capture window is recorded.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Returns the signal asking the process for diagnostics.
+    pub(crate) fn signal_number(&self) -> c_int {
+        self.signal
+    }
+}
+
+impl Default for DiagnosticDump {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The diagnostics recorded by a dump step of a termination plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// The signal sent to the process.
+    pub signal: c_int,
+    /// The bytes written on the recorded stream during the capture window.
+    /// `None` if the guard does not read the output of the process, empty if the stream is not piped.
+    pub output: Option<Vec<u8>>,
+    /// The bytes appended to the dump file during the capture window.
+    /// `None` if no dump file was set or it could not be read.
+    pub file: Option<Vec<u8>>,
+}
+
+/// Runs a dump step.
+///
+/// # Arguments
+///
+/// * `child` - The process to dump.
+/// * `dump` - The dump to perform.
+/// * `output` - The pump reading the output of the process, if any.
+///
+/// # Returns
+///
+/// * `Ok((Diagnostics, Some(ExitStatus)))` - If the process exited during the capture window.
+/// * `Ok((Diagnostics, None))` - If the capture window elapsed.
+/// * `Err(Error)` - If the signal could not be sent or waiting for the process failed.
+pub(crate) fn run_dump(
+    child: &mut Child,
+    dump: &DiagnosticDump,
+    output: Option<&OutputPump>,
+    wait_timeout: impl FnOnce(&mut Child, Duration) -> io::Result<ExitStatus>,
+) -> Result<(Diagnostics, Option<ExitStatus>), Error> {
+    let offset = dump.file.as_deref().map(file_len);
+    if let Some(output) = output {
+        output.record(dump.stream);
+    }
+
+    let deadline = Instant::now() + dump.window;
+    let waited = if unsafe { kill(child.id() as pid_t, dump.signal) } != 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        wait_timeout(child, dump.window)
+    };
+    let status = match waited {
+        Ok(status) => {
+            // The diagnostics written just before exiting may not be read yet
+            if let Some(output) = output {
+                output.settle(deadline.saturating_duration_since(Instant::now()));
+            }
+            Some(status)
+        }
+        Err(e) if e.kind() == io::ErrorKind::TimedOut => None,
+        Err(e) => {
+            if let Some(output) = output {
+                output.take_recorded();
+            }
+            return Err(e.into());
+        }
+    };
+
+    let diagnostics = Diagnostics {
+        signal: dump.signal,
+        output: output.map(OutputPump::take_recorded),
+        file: dump
+            .file
+            .as_deref()
+            .zip(offset)
+            .and_then(|(path, offset)| read_from(path, offset).ok()),
+    };
+
+    Ok((diagnostics, status))
+}
+
+/// Returns the length of a file, 0 if it does not exist yet.
+fn file_len(path: &Path) -> u64 {
+    path.metadata().map(|metadata| metadata.len()).unwrap_or(0)
+}
+
+/// Reads a file from `offset`, or from its start if it was truncated below `offset`.
+fn read_from(path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() >= offset {
+        file.seek(SeekFrom::Start(offset))?;
+    }
+
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
@@ -1,5 +1,6 @@
 use std::io;
-use thiserror;
+
+use crate::output::RecentOutput;
 
 // note if you have a linter error on "thiserror::Error": https://stackoverflow.com/questions/72698907/proc-macro-not-found
 
@@ -16,6 +17,12 @@ pub enum Error {
     #[error("Failed to send Ctrl-C: {0}")]
     FailedToSendCtrlC(u32),
 
+    /// Indicates that the current process is not allowed to signal a process, such as a process running as another
+    /// user without the privilege to signal it.
+    /// The associated value is the process ID.
+    #[error("Permission denied to signal process {0}")]
+    PermissionDenied(u32),
+
     /// Indicates that waiting for a process failed.
     /// The associated value is the error code from the failed wait.
     #[error("Wait failed: {0}")]
@@ -25,6 +32,10 @@ pub enum Error {
     #[error("Unknown error")]
     UnknownError(),
 
+    /// Indicates that the stdin of the process cannot be closed or written to, because it was not spawned with `Stdio::piped()`.
+    #[error("Stdin is not piped")]
+    StdinNotPiped,
+
     /// Indicates that the target process was forcefully terminated.
     #[error("Forcefully terminate")]
     ForcefullyTerminate(),
@@ -33,4 +44,29 @@ pub enum Error {
     /// The associated value is the underlying `io::Error`.
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
+
+    /// Indicates that no line of the output of a process matched before the timeout elapsed or the output ended.
+    /// The associated value is the output read while looking for the line.
+    #[error("Line not found in the output:\n{0}")]
+    LineNotFound(RecentOutput),
+
+    /// Indicates that an error occurred with a process whose most recent output is kept.
+    /// The associated values are the underlying `Error` and the most recent output of the process.
+    #[error("{0}\nRecent output:\n{1}")]
+    WithRecentOutput(Box<Error>, RecentOutput),
+
+    /// Indicates that a line of a Procfile or of a `.env` file could not be parsed.
+    /// The associated values are the line number, starting at 1, and the line.
+    #[error("Invalid line {0}: {1}")]
+    InvalidLine(usize, String),
+}
+
+impl Error {
+    /// Converts the error of a signal sent to the process `pid`, `EPERM` becoming `Error::PermissionDenied`.
+    pub(crate) fn from_signal(error: io::Error, pid: u32) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied(pid),
+            _ => Error::Io(error),
+        }
+    }
 }
@@ -0,0 +1,305 @@
+use std::{fmt, process::ExitStatus, time::Duration};
+
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+
+#[cfg(unix)]
+use crate::rlimit::Rlimit;
+
+/// Enum representing how a process ended, decoded from its `ExitStatus`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitKind {
+    /// The process exited with a code.
+    Exited(i32),
+    /// The process was terminated by a signal.
+    #[cfg(unix)]
+    Signaled {
+        /// The signal number.
+        signal: i32,
+        /// Whether the process dumped a core.
+        core_dumped: bool,
+    },
+    /// The process was stopped by a signal.
+    #[cfg(unix)]
+    Stopped(i32),
+    /// The process was resumed by `SIGCONT`.
+    #[cfg(unix)]
+    Continued,
+    /// The status could not be decoded.
+    Unknown,
+}
+
+impl From<ExitStatus> for ExitKind {
+    fn from(status: ExitStatus) -> Self {
+        if let Some(code) = status.code() {
+            return ExitKind::Exited(code);
+        }
+
+        #[cfg(unix)]
+        {
+            if let Some(signal) = status.signal() {
+                return ExitKind::Signaled {
+                    signal,
+                    core_dumped: status.core_dumped(),
+                };
+            }
+            if let Some(signal) = status.stopped_signal() {
+                return ExitKind::Stopped(signal);
+            }
+            if status.continued() {
+                return ExitKind::Continued;
+            }
+        }
+
+        ExitKind::Unknown
+    }
+}
+
+/// Enum representing the steps of a termination plan that may end a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscalationStep {
+    /// A Ctrl+C signal, `SIGINT` on Unix.
+    CtrlC,
+    /// A kill, `SIGKILL` on Unix.
+    Kill,
+    /// Another signal, such as the signal of a dump step.
+    #[cfg(unix)]
+    Signal(i32),
+}
+
+impl EscalationStep {
+    /// Returns whether the step sends the signal that terminated the process.
+    #[cfg(unix)]
+    fn sends(&self, signal: i32) -> bool {
+        let sent = match self {
+            EscalationStep::CtrlC => libc::SIGINT,
+            EscalationStep::Kill => libc::SIGKILL,
+            EscalationStep::Signal(sent) => *sent,
+        };
+        sent == signal
+    }
+}
+
+/// A step of the termination plan that ended the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Escalation {
+    /// The step.
+    pub step: EscalationStep,
+    /// The time elapsed between the start of the termination and the step, the grace period the process was given.
+    pub after: Duration,
+}
+
+/// Structured interpretation of the exit status of a process, telling apart a process ended by its guard from a process
+/// that exited on its own or was killed by someone else.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use proc_guard::{ExitInfo, ExitKind};
+///
+/// let status = if cfg!(target_os = "windows") {
+///     Command::new("cmd").args(["/C", "exit 3"]).status()?
+/// } else {
+///     Command::new("sh").args(["-c", "exit 3"]).status()?
+/// };
+///
+/// let info = ExitInfo::from(status);
+/// assert_eq!(info.kind, ExitKind::Exited(3));
+/// assert_eq!(info.to_string(), "exited with code 3");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitInfo {
+    /// The raw exit status.
+    pub status: ExitStatus,
+    /// How the process ended.
+    pub kind: ExitKind,
+    /// The step of the termination plan that ended the process, `None` if the process exited on its own or was
+    /// terminated externally.
+    pub escalation: Option<Escalation>,
+}
+
+impl ExitInfo {
+    /// Interprets the status of a process that went through the given steps of a termination plan.
+    ///
+    /// A signal is attributed to the last step sending it. On other platforms, the kill is attributed to the last
+    /// kill step since the exit code does not tell.
+    pub(crate) fn attribute(status: ExitStatus, escalations: &[Escalation]) -> Self {
+        let kind = ExitKind::from(status);
+
+        let escalation = match kind {
+            #[cfg(unix)]
+            ExitKind::Signaled { signal, .. } => escalations
+                .iter()
+                .rev()
+                .find(|escalation| escalation.step.sends(signal)),
+            #[cfg(not(unix))]
+            ExitKind::Exited(_) => escalations
+                .iter()
+                .rev()
+                .find(|escalation| escalation.step == EscalationStep::Kill),
+            _ => None,
+        };
+
+        ExitInfo {
+            status,
+            kind,
+            escalation: escalation.copied(),
+        }
+    }
+
+    /// Returns whether the process exited with code 0.
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// Returns the exit code a shell reports for the process, `128 + signal` for a process terminated by a signal,
+    /// which is what a wrapper exits with to be transparent.
+    pub fn shell_code(&self) -> i32 {
+        match self.kind {
+            ExitKind::Exited(code) => code,
+            #[cfg(unix)]
+            ExitKind::Signaled { signal, .. } => 128 + signal,
+            _ => 1,
+        }
+    }
+
+    /// Returns whether the process was ended by a step of its termination plan.
+    pub fn ended_by_guard(&self) -> bool {
+        self.escalation.is_some()
+    }
+
+    /// Returns the kernel resource limit the process exceeded, if it was terminated by `SIGXCPU` or `SIGXFSZ`, see
+    /// `Rlimits`. Such a signal sent by another process is reported as well.
+    #[cfg(unix)]
+    pub fn exceeded_rlimit(&self) -> Option<Rlimit> {
+        match self.kind {
+            ExitKind::Signaled { signal, .. } if self.escalation.is_none() => {
+                Rlimit::from_signal(signal)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether the process made a syscall denied by its `Sandbox`, which kills it with `SIGSYS`.
+    /// Such a signal sent by another process is reported as well.
+    #[cfg(target_os = "linux")]
+    pub fn violated_seccomp(&self) -> bool {
+        matches!(
+            self.kind,
+            ExitKind::Signaled { signal: libc::SIGSYS, .. } if self.escalation.is_none()
+        )
+    }
+}
+
+impl From<ExitStatus> for ExitInfo {
+    fn from(status: ExitStatus) -> Self {
+        Self::attribute(status, &[])
+    }
+}
+
+impl fmt::Display for ExitInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ExitKind::Exited(code) => write!(f, "exited with code {}", code)?,
+            #[cfg(unix)]
+            ExitKind::Signaled {
+                signal,
+                core_dumped,
+            } => {
+                match self.escalation {
+                    Some(_) => write!(f, "terminated by {}", SignalName(signal))?,
+                    None => write!(f, "killed by {}", SignalName(signal))?,
+                }
+                if let Some(rlimit) = self.exceeded_rlimit() {
+                    write!(f, ", {} exceeded", rlimit)?;
+                }
+                #[cfg(target_os = "linux")]
+                if self.violated_seccomp() {
+                    write!(f, ", seccomp violation")?;
+                }
+                if core_dumped {
+                    write!(f, " (core dumped)")?;
+                }
+            }
+            #[cfg(unix)]
+            ExitKind::Stopped(signal) => write!(f, "stopped by {}", SignalName(signal))?,
+            #[cfg(unix)]
+            ExitKind::Continued => write!(f, "continued")?,
+            ExitKind::Unknown => write!(f, "{}", self.status)?,
+        }
+
+        if let Some(escalation) = self.escalation {
+            #[cfg(not(unix))]
+            if escalation.step == EscalationStep::Kill {
+                write!(f, ", killed")?;
+            }
+            write!(f, " after {} grace", FormattedDuration(escalation.after))?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a signal by its name, such as `SIGKILL`.
+#[cfg(unix)]
+struct SignalName(i32);
+
+#[cfg(unix)]
+impl fmt::Display for SignalName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0 {
+            libc::SIGHUP => "SIGHUP",
+            libc::SIGINT => "SIGINT",
+            libc::SIGQUIT => "SIGQUIT",
+            libc::SIGILL => "SIGILL",
+            libc::SIGTRAP => "SIGTRAP",
+            libc::SIGABRT => "SIGABRT",
+            libc::SIGBUS => "SIGBUS",
+            libc::SIGFPE => "SIGFPE",
+            libc::SIGKILL => "SIGKILL",
+            libc::SIGUSR1 => "SIGUSR1",
+            libc::SIGSEGV => "SIGSEGV",
+            libc::SIGUSR2 => "SIGUSR2",
+            libc::SIGPIPE => "SIGPIPE",
+            libc::SIGALRM => "SIGALRM",
+            libc::SIGTERM => "SIGTERM",
+            libc::SIGCHLD => "SIGCHLD",
+            libc::SIGCONT => "SIGCONT",
+            libc::SIGSTOP => "SIGSTOP",
+            libc::SIGTSTP => "SIGTSTP",
+            libc::SIGTTIN => "SIGTTIN",
+            libc::SIGTTOU => "SIGTTOU",
+            libc::SIGURG => "SIGURG",
+            libc::SIGXCPU => "SIGXCPU",
+            libc::SIGXFSZ => "SIGXFSZ",
+            libc::SIGVTALRM => "SIGVTALRM",
+            libc::SIGPROF => "SIGPROF",
+            libc::SIGWINCH => "SIGWINCH",
+            libc::SIGIO => "SIGIO",
+            libc::SIGSYS => "SIGSYS",
+            signal => return write!(f, "signal {}", signal),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Displays a duration rounded for humans, such as `5s`, `1.5s` or `200ms`.
+struct FormattedDuration(Duration);
+
+impl fmt::Display for FormattedDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = self.0.as_millis();
+        if millis < 1000 {
+            write!(f, "{}ms", millis)
+        } else if millis % 1000 < 50 || millis % 1000 >= 950 {
+            write!(f, "{}s", (millis + 50) / 1000)
+        } else {
+            write!(f, "{:.1}s", self.0.as_secs_f64())
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    mem,
+    os::fd::{AsRawFd, FromRawFd},
+    ptr,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread::{self, JoinHandle},
+};
+
+use libc::{
+    c_int, c_void, kill, pid_t, sigaction, SIGHUP, SIGINT, SIGTERM, SIGUSR1, SIGUSR2, SIGWINCH,
+};
+
+use crate::{error::Error, pty};
+
+/// The signals a transparent wrapper forwards to its child.
+pub const DEFAULT_FORWARDED_SIGNALS: [c_int; 6] =
+    [SIGINT, SIGTERM, SIGHUP, SIGWINCH, SIGUSR1, SIGUSR2];
+
+/// The write end of the self-pipe of the installed forwarder, -1 if no forwarder is installed.
+static PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// The byte telling the forwarding thread to stop, no signal has the number 0.
+const STOP: u8 = 0;
+
+/// Relays the signals received by the current process to the processes of registered guards, as a transparent
+/// wrapper does, instead of the signals being handled by the current process only.
+///
+/// The handlers write the signals to a pipe read by a background thread, which sends them to the registered
+/// processes. A process spawned in a pseudo-terminal gets the window size of the terminal of the current process
+/// instead of `SIGWINCH`, the kernel signalling its whole foreground process group.
+/// Only one forwarder can be installed at a time. Uninstalling it, or dropping it, restores the previous handlers.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use proc_guard::{ProcGuard, ProcessTermination, SignalForwarder, DEFAULT_FORWARDED_SIGNALS};
+///
+/// let forwarder = SignalForwarder::install(&DEFAULT_FORWARDED_SIGNALS)?;
+/// let guard = ProcGuard::spawn(Command::new("sleep").arg("1"), ProcessTermination::Wait)?
+///     .with_signal_forwarder(&forwarder);
+///
+/// // A SIGTERM received now is sent to `sleep`
+/// guard.terminate()?;
+/// forwarder.uninstall()?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SignalForwarder {
+    targets: Arc<Mutex<Targets>>,
+    previous: Vec<(c_int, sigaction)>,
+    pipe: Option<File>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug, Default)]
+struct Targets {
+    next_id: u64,
+    targets: Vec<Target>,
+}
+
+/// A process the signals are forwarded to.
+#[derive(Debug)]
+struct Target {
+    id: u64,
+    pid: pid_t,
+    pty_master: Option<File>,
+}
+
+impl SignalForwarder {
+    /// Installs handlers for `signals` and starts relaying them to the registered guards.
+    ///
+    /// # Arguments
+    ///
+    /// * `signals` - The signals to forward, such as `DEFAULT_FORWARDED_SIGNALS`.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If a forwarder is already installed, a signal cannot be handled such as
+    ///   `SIGKILL`, or the pipe cannot be created. No handler is left installed.
+    pub fn install(signals: &[c_int]) -> Result<Self, Error> {
+        let (reader, writer) = self_pipe()?;
+        if PIPE
+            .compare_exchange(-1, writer.as_raw_fd(), Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "a signal forwarder is already installed",
+            )
+            .into());
+        }
+
+        let targets = Arc::new(Mutex::new(Targets::default()));
+        let thread_targets = targets.clone();
+        let mut forwarder = SignalForwarder {
+            targets,
+            previous: Vec::with_capacity(signals.len()),
+            pipe: Some(writer),
+            thread: Some(thread::spawn(move || forward(reader, &thread_targets))),
+        };
+
+        for &signal in signals {
+            unsafe {
+                let mut action: sigaction = mem::zeroed();
+                action.sa_sigaction = on_signal as extern "C" fn(c_int) as usize;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+                let mut previous: sigaction = mem::zeroed();
+                if libc::sigaction(signal, &action, &mut previous) != 0 {
+                    let error = io::Error::last_os_error();
+                    let _ = forwarder.restore();
+                    return Err(error.into());
+                }
+                forwarder.previous.push((signal, previous));
+            }
+        }
+
+        Ok(forwarder)
+    }
+
+    /// Restores the previous handlers and stops relaying signals.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If a previous handler cannot be restored.
+    pub fn uninstall(mut self) -> Result<(), Error> {
+        self.restore()
+    }
+
+    /// Registers a process the signals are forwarded to, until the returned registration is dropped.
+    pub(crate) fn register(&self, pid: u32, pty_master: Option<File>) -> Forwarding {
+        let mut targets = lock(&self.targets);
+        let id = targets.next_id;
+        targets.next_id += 1;
+        targets.targets.push(Target {
+            id,
+            pid: pid as pid_t,
+            pty_master,
+        });
+
+        Forwarding {
+            id,
+            targets: self.targets.clone(),
+        }
+    }
+
+    /// Restores the previous handlers, then stops the forwarding thread.
+    fn restore(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for (signal, previous) in self.previous.drain(..).rev() {
+            if unsafe { libc::sigaction(signal, &previous, ptr::null_mut()) } != 0 {
+                result = Err(io::Error::last_os_error().into());
+            }
+        }
+
+        if let Some(pipe) = self.pipe.take() {
+            let _ = write_byte(pipe.as_raw_fd(), STOP);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+            // A handler still running writes to the pipe until the pipe is unpublished
+            PIPE.store(-1, Ordering::SeqCst);
+        }
+        result
+    }
+}
+
+impl Drop for SignalForwarder {
+    /// Restores the previous handlers.
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// The registration of a guarded process to a `SignalForwarder`, removed on drop.
+#[derive(Debug)]
+pub(crate) struct Forwarding {
+    id: u64,
+    targets: Arc<Mutex<Targets>>,
+}
+
+impl Drop for Forwarding {
+    fn drop(&mut self) {
+        lock(&self.targets)
+            .targets
+            .retain(|target| target.id != self.id);
+    }
+}
+
+fn lock(targets: &Mutex<Targets>) -> MutexGuard<'_, Targets> {
+    targets.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+extern "C" fn on_signal(signal: c_int) {
+    // `write` may set `errno`, which the interrupted code must still see unchanged
+    let errno = unsafe { *errno_location() };
+    let pipe = PIPE.load(Ordering::SeqCst);
+    if pipe >= 0 {
+        // A full pipe drops the signal rather than blocking the handler
+        let _ = write_byte(pipe, signal as u8);
+    }
+    unsafe { *errno_location() = errno };
+}
+
+/// Returns the location of `errno` for the current thread.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "emscripten"))]
+unsafe fn errno_location() -> *mut c_int {
+    libc::__errno_location()
+}
+
+/// Returns the location of `errno` for the current thread.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+))]
+unsafe fn errno_location() -> *mut c_int {
+    libc::__error()
+}
+
+/// Returns the location of `errno` for the current thread.
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+unsafe fn errno_location() -> *mut c_int {
+    libc::__errno()
+}
+
+/// Writes a byte to a file descriptor with `write`, which is async-signal-safe.
+fn write_byte(fd: c_int, byte: u8) -> io::Result<()> {
+    if unsafe { libc::write(fd, &byte as *const u8 as *const c_void, 1) } != 1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Creates a pipe whose ends are closed on exec, the write end being non-blocking.
+fn self_pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (reader, writer) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+    for fd in fds {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    let flags = unsafe { libc::fcntl(fds[1], libc::F_GETFL) };
+    if flags == -1 || unsafe { libc::fcntl(fds[1], libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1
+    {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((reader, writer))
+}
+
+/// Reads the signals written by the handlers and sends them to the registered processes, until told to stop.
+fn forward(mut reader: File, targets: &Mutex<Targets>) {
+    let mut buffer = [0; 64];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return,
+        };
+
+        for &byte in &buffer[..read] {
+            if byte == STOP {
+                return;
+            }
+
+            let signal = c_int::from(byte);
+            for target in &lock(targets).targets {
+                if signal == SIGWINCH {
+                    if let Some(master) = &target.pty_master {
+                        if pty::copy_window_size(master).is_ok() {
+                            continue;
+                        }
+                    }
+                }
+                // The process may have exited, there is no one to report the error to
+                unsafe { kill(target.pid, signal) };
+            }
+        }
+    }
+}
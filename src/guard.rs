@@ -1,13 +1,55 @@
 use std::{
-    io,
+    fs::File,
+    io::{self, Write},
     mem::ManuallyDrop,
+    panic,
     process::{Child, Command, ExitStatus},
-    time::Duration,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use child_wait_timeout::ChildWT;
 
-use crate::{error::Error, send_ctrl_c};
+#[cfg(unix)]
+use crate::dump::{self, Diagnostics};
+#[cfg(unix)]
+use crate::forward::{Forwarding, SignalForwarder};
+#[cfg(target_os = "linux")]
+use crate::monitor::{sample_resources, ResourceLimits};
+#[cfg(unix)]
+use crate::pause::{self, PauseMethod};
+#[cfg(unix)]
+use crate::pty;
+#[cfg(target_os = "linux")]
+use crate::scheduling::SchedulingInfo;
+#[cfg(target_os = "linux")]
+use crate::snapshot::{KillSnapshot, ProcSnapshot};
+#[cfg(target_os = "linux")]
+use crate::subreaper::{ReapedOrphan, Subreaper, Tracking};
+#[cfg(target_os = "linux")]
+use crate::tree::{TreeKill, TreeOrder};
+#[cfg(unix)]
+use crate::usage::{self, ResourceUsage};
+use crate::{
+    error::Error,
+    exit::{Escalation, EscalationStep, ExitInfo},
+    outcome::{TerminationOutcome, TerminationReason},
+    output::{LineMatcher, OutputLine, OutputMode, OutputPump, RecentOutput, RecentOutputLimit},
+    plan::{Step, TerminationPlan, TerminationStep},
+    router::OutputRouter,
+    send_ctrl_c,
+    spawn::{self, SpawnOptions, Spawned},
+};
+
+/// Interval at which the idle-output watchdog is checked, unless the idle timeout is shorter.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Maximum time to wait for the output of a process to be fully read once it ended.
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
 
 /// Enum representing the various termination strategies available for a process guard.
 #[derive(Debug, Clone, Copy)]
@@ -15,6 +57,7 @@ pub enum ProcessTermination {
     /// Wait indefinitely for the process to exit.
     Wait,
     /// Wait for a specified duration for the process to exit.
+    /// The termination fails with `Error::Timeout` if the process did not exit in time.
     WaitTimeout(Duration),
     /// Wait for a specified duration, then kill the process if it hasn't exited.
     WaitTimeoutKill(Duration),
@@ -23,6 +66,7 @@ pub enum ProcessTermination {
     /// Send a Ctrl+C signal and wait indefinitely for the process to exit.
     CtrlCWait,
     /// Send a Ctrl+C signal and wait for a specified duration for the process to exit.
+    /// The termination fails with `Error::Timeout` if the process did not exit in time.
     CtrlCWaitTimeout(Duration),
     /// Send a Ctrl+C signal, wait for a specified duration, and then kill the process if it hasn't exited.
     CtrlCWaitTimeoutKill(Duration),
@@ -30,6 +74,22 @@ pub enum ProcessTermination {
     Kill,
     /// Kill the process immediately and wait indefinitely for the process to exit.
     KillWait,
+    /// Close the stdin of the process and does NOT wait.
+    CloseStdin,
+    /// Close the stdin of the process and wait indefinitely for the process to exit.
+    CloseStdinWait,
+    /// Close the stdin of the process and wait for a specified duration for the process to exit.
+    CloseStdinWaitTimeout(Duration),
+    /// Close the stdin of the process, wait for a specified duration, and then kill the process if it hasn't exited.
+    CloseStdinWaitTimeoutKill(Duration),
+    /// Write the given bytes, such as a quit command, to the stdin of the process and does NOT wait.
+    WriteStdin(&'static [u8]),
+    /// Write the given bytes to the stdin of the process and wait indefinitely for the process to exit.
+    WriteStdinWait(&'static [u8]),
+    /// Write the given bytes to the stdin of the process and wait for a specified duration for the process to exit.
+    WriteStdinWaitTimeout(&'static [u8], Duration),
+    /// Write the given bytes to the stdin of the process, wait for a specified duration, and then kill the process if it hasn't exited.
+    WriteStdinWaitTimeoutKill(&'static [u8], Duration),
 }
 
 /// Struct representing a guard for a child process.
@@ -38,7 +98,35 @@ pub enum ProcessTermination {
 pub struct ProcGuard {
     child: ManuallyDrop<Child>,
     dropped: bool,
-    termination: ProcessTermination,
+    termination: TerminationPlan,
+    #[cfg(target_os = "linux")]
+    resource_limits: Option<ResourceLimits>,
+    output: Option<OutputPump>,
+    idle_timeout: Option<Duration>,
+    recent_output: Option<RecentOutputLimit>,
+    unread_lines: bool,
+    pty_master: Option<File>,
+    #[cfg(unix)]
+    diagnostics: Vec<Diagnostics>,
+    #[cfg(target_os = "linux")]
+    kill_snapshot: Option<KillSnapshot>,
+    #[cfg(target_os = "linux")]
+    snapshots: Vec<ProcSnapshot>,
+    escalations: Vec<Escalation>,
+    #[cfg(unix)]
+    paused: Option<PauseMethod>,
+    #[cfg(unix)]
+    forwarding: Option<Forwarding>,
+    #[cfg(target_os = "linux")]
+    subreaper: Option<Tracking>,
+    #[cfg(target_os = "linux")]
+    orphans: Vec<ReapedOrphan>,
+    #[cfg(target_os = "linux")]
+    tree_kill: Option<TreeOrder>,
+    spawned_at: Instant,
+    ended_at: Option<Instant>,
+    #[cfg(unix)]
+    usage: Option<ResourceUsage>,
 }
 
 impl ProcGuard {
@@ -47,7 +135,7 @@ impl ProcGuard {
     /// # Arguments
     ///
     /// * `child` - The child process to guard.
-    /// * `termination` - The termination strategy to use when dropping the guard.
+    /// * `termination` - The termination strategy to use when dropping the guard, a `ProcessTermination` or a `TerminationPlan`.
     ///
     /// # Returns
     ///
@@ -78,11 +166,39 @@ impl ProcGuard {
     /// #     Ok(())
     /// # }
     /// ```
-    pub fn new(child: Child, termination: ProcessTermination) -> Self {
+    pub fn new(child: Child, termination: impl Into<TerminationPlan>) -> Self {
         ProcGuard {
             child: ManuallyDrop::new(child),
-            termination: termination,
+            termination: termination.into(),
             dropped: false,
+            #[cfg(target_os = "linux")]
+            resource_limits: None,
+            output: None,
+            idle_timeout: None,
+            recent_output: None,
+            unread_lines: false,
+            pty_master: None,
+            #[cfg(unix)]
+            diagnostics: Vec::new(),
+            #[cfg(target_os = "linux")]
+            kill_snapshot: None,
+            #[cfg(target_os = "linux")]
+            snapshots: Vec::new(),
+            escalations: Vec::new(),
+            #[cfg(unix)]
+            paused: None,
+            #[cfg(unix)]
+            forwarding: None,
+            #[cfg(target_os = "linux")]
+            subreaper: None,
+            #[cfg(target_os = "linux")]
+            orphans: Vec::new(),
+            #[cfg(target_os = "linux")]
+            tree_kill: None,
+            spawned_at: Instant::now(),
+            ended_at: None,
+            #[cfg(unix)]
+            usage: None,
         }
     }
 
@@ -91,7 +207,7 @@ impl ProcGuard {
     /// # Arguments
     ///
     /// * `command` - The command to create the child process to guard.
-    /// * `termination` - The termination strategy to use when dropping the guard.
+    /// * `termination` - The termination strategy to use when dropping the guard, a `ProcessTermination` or a `TerminationPlan`.
     ///
     /// # Returns
     ///
@@ -126,12 +242,437 @@ impl ProcGuard {
     /// #     Ok(())
     /// # }
     /// ```
-    pub fn spawn(command: &mut Command, termination: ProcessTermination) -> io::Result<Self> {
-        Ok(ProcGuard {
-            child: ManuallyDrop::new(command.spawn()?),
-            termination: termination,
-            dropped: false,
-        })
+    pub fn spawn(
+        command: &mut Command,
+        termination: impl Into<TerminationPlan>,
+    ) -> io::Result<Self> {
+        Ok(Self::new(command.spawn()?, termination))
+    }
+
+    /// Spawn the desired process with additional options into a new `ProcGuard`.
+    ///
+    /// The options are applied to `command`, which should not be reused to spawn other processes.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to create the child process to guard.
+    /// * `termination` - The termination strategy to use when dropping the guard, a `ProcessTermination` or a `TerminationPlan`.
+    /// * `options` - The options applied when spawning the process.
+    ///
+    /// # Returns
+    ///
+    /// * `ProcGuard` - A guard around the child process.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::Command;
+    /// use proc_guard::{ProcGuard, ProcessTermination, SpawnOptions};
+    ///
+    /// # #[cfg(unix)]
+    /// # {
+    /// let guard = ProcGuard::spawn_with(
+    ///     Command::new("tty").arg("-s"),
+    ///     ProcessTermination::Wait,
+    ///     SpawnOptions::new().pty(true),
+    /// )?;
+    ///
+    /// // `tty -s` succeeds only when its stdin is a terminal.
+    /// assert!(guard.terminate()?.expect("No exit status").success());
+    /// # }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn spawn_with(
+        command: &mut Command,
+        termination: impl Into<TerminationPlan>,
+        options: SpawnOptions,
+    ) -> io::Result<Self> {
+        let Spawned {
+            child,
+            #[cfg(unix)]
+            pty_master,
+            #[cfg(target_os = "linux")]
+            subreaper,
+        } = spawn::spawn(command, &options)?;
+
+        let mut guard = Self::new(child, termination);
+        #[cfg(unix)]
+        {
+            guard.pty_master = pty_master;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            guard.subreaper = subreaper;
+        }
+        Ok(guard)
+    }
+
+    /// Sets the resource limits enforced by `supervise`.
+    ///
+    /// When a limit is exceeded, the termination strategy is applied and `supervise` reports which limit was exceeded.
+    /// The limits are only checked while `supervise` runs: a guard kept alive while the current process talks to the
+    /// process, then terminated or dropped, never enforces them. Use `SpawnOptions::rlimits` for limits enforced by
+    /// the kernel whatever the guard does.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The limits sampled from `/proc/<pid>` while the process is supervised.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    /// use proc_guard::{ProcGuard, ProcessTermination, ResourceLimits};
+    ///
+    /// let guard = ProcGuard::spawn(Command::new("sleep").arg("1"), ProcessTermination::KillWait)?
+    ///     .with_resource_limits(
+    ///         ResourceLimits::new()
+    ///             .max_rss(1 << 30)
+    ///             .max_cpu_time(Duration::from_secs(10)),
+    ///     );
+    /// let outcome = guard.supervise()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = Some(limits);
+        self
+    }
+
+    /// Takes a snapshot of `/proc` for the process and its descendants right before a kill step of the termination plan,
+    /// so that the state of a hung process is not lost when it is killed.
+    ///
+    /// The snapshots are returned in the termination outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - Where the snapshots go besides the termination outcome.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    /// use proc_guard::{KillSnapshot, ProcGuard, ProcessTermination};
+    ///
+    /// let guard = ProcGuard::spawn(
+    ///     Command::new("sleep").arg("5"),
+    ///     ProcessTermination::WaitTimeoutKill(Duration::from_millis(100)),
+    /// )?
+    /// .with_kill_snapshot(KillSnapshot::new().directory(std::env::temp_dir()));
+    /// let outcome = guard.terminate_with_output()?;
+    /// println!("{}", outcome.snapshots[0]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn with_kill_snapshot(mut self, snapshot: KillSnapshot) -> Self {
+        self.kill_snapshot = Some(snapshot);
+        self
+    }
+
+    /// Registers the process to a `SignalForwarder`, so that the signals received by the current process are relayed to it.
+    ///
+    /// The process stops receiving signals once the guard starts terminating it or is released,
+    /// so that a signal never reaches another process reusing its pid.
+    ///
+    /// # Arguments
+    ///
+    /// * `forwarder` - The installed forwarder.
+    #[cfg(unix)]
+    pub fn with_signal_forwarder(mut self, forwarder: &SignalForwarder) -> Self {
+        let pty_master = self
+            .pty_master
+            .as_ref()
+            .and_then(|master| master.try_clone().ok());
+        self.forwarding = Some(forwarder.register(self.child.id(), pty_master));
+        self
+    }
+
+    /// Links the orphaned descendants of the process to this guard, see `Subreaper`.
+    ///
+    /// Only the orphans whose ancestry was recorded before they were orphaned are linked, `SpawnOptions::subreaper`
+    /// also links the daemons that double-fork right away.
+    /// The Ctrl+C and kill steps of the termination plan are also sent to the orphans and their descendants,
+    /// and the orphans reaped are reported in the termination outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `subreaper` - The subreaper collecting the orphans.
+    #[cfg(target_os = "linux")]
+    pub fn with_subreaper(mut self, subreaper: &Subreaper) -> Self {
+        self.subreaper = Some(subreaper.register(self.child.id()));
+        self
+    }
+
+    /// Sends the Ctrl+C and kill steps of the termination plan to every descendant of the process too, as `SIGINT`
+    /// and `SIGKILL`.
+    ///
+    /// The descendants are found from the parent of every process in `/proc`, so that the processes that left the
+    /// process group of the process, such as by calling `setsid`, are terminated without cgroups. The descendants seen
+    /// by a step still receive the following steps once reparented to init, and a process is only signaled if its
+    /// start time shows that its pid was not reused.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - Whether parents are signaled before or after their children.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::Command;
+    /// use proc_guard::{ProcGuard, ProcessTermination, TreeOrder};
+    ///
+    /// let mut command = Command::new("sh");
+    /// command.args(["-c", "setsid sleep 30 & exec sleep 30"]);
+    /// let guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)?
+    ///     .with_tree_kill(TreeOrder::BottomUp);
+    /// guard.terminate()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn with_tree_kill(mut self, order: TreeOrder) -> Self {
+        self.tree_kill = Some(order);
+        self
+    }
+
+    /// Makes the guard own the piped stdout and stderr of the process and read them in the background.
+    ///
+    /// A process spawned with `Stdio::piped()` blocks once its pipes are full, so waiting for it without reading its output might never end.
+    /// With this mode the pipes are drained during every wait step of the termination strategy.
+    /// Streams that were not spawned with `Stdio::piped()` are left untouched.
+    /// Calling this method again has no effect, the pipes are already owned by the guard.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - What to do with the output of the process.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use proc_guard::{OutputMode, ProcGuard, ProcessTermination};
+    ///
+    /// let mut command = if cfg!(target_os = "windows") {
+    ///     let mut command = Command::new("cmd");
+    ///     command.args(["/C", "echo hello"]);
+    ///     command
+    /// } else {
+    ///     let mut command = Command::new("echo");
+    ///     command.arg("hello");
+    ///     command
+    /// };
+    ///
+    /// let guard = ProcGuard::spawn(command.stdout(Stdio::piped()), ProcessTermination::KillWait)?
+    ///     .with_output(OutputMode::Capture);
+    /// let outcome = guard.supervise()?;
+    /// assert!(outcome.output.expect("No captured output").stdout.starts_with(b"hello"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_output(mut self, mode: OutputMode) -> Self {
+        self._output(mode);
+        self
+    }
+
+    /// Makes the guard own the piped stdout and stderr of the process and send their lines to an output router,
+    /// prefixed with `name`.
+    ///
+    /// The pipes are drained like with `with_output`, until the router holds too many lines that its sink did not
+    /// write yet. The reading then waits for the sink, so that the memory does not grow without limit.
+    /// Terminating the guard waits for the output to be fully read, at most one second, so that the last lines are routed.
+    /// Called after `with_output`, the output is both routed and handled as the mode of `with_output` says. Called
+    /// again, the output is routed to the new router instead, the output of the previous one ending as if the process
+    /// closed its pipes. In both cases only the output read from then on is routed, this method should be called right
+    /// after spawning the process.
+    ///
+    /// # Arguments
+    ///
+    /// * `router` - The router writing the lines.
+    /// * `name` - The name prefixing the lines of the process.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use proc_guard::{OutputRouter, ProcGuard, ProcessTermination};
+    ///
+    /// let router = OutputRouter::stdout().colors(true);
+    /// let mut command = if cfg!(target_os = "windows") {
+    ///     let mut command = Command::new("cmd");
+    ///     command.args(["/C", "echo ready"]);
+    ///     command
+    /// } else {
+    ///     let mut command = Command::new("echo");
+    ///     command.arg("ready");
+    ///     command
+    /// };
+    ///
+    /// let worker = ProcGuard::spawn(command.stdout(Stdio::piped()), ProcessTermination::Wait)?
+    ///     .with_output_router(&router, "worker");
+    /// worker.terminate()?;
+    /// router.flush();
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_output_router(mut self, router: &OutputRouter, name: impl Into<String>) -> Self {
+        self._output(OutputMode::Discard).route(router.route(name));
+        self
+    }
+
+    /// Sets the idle-output timeout enforced by `supervise`.
+    ///
+    /// Like the resource limits, the timeout is only checked while `supervise` runs.
+    /// When the process does not write a single byte on its piped stdout or stderr during `timeout`,
+    /// the termination strategy is applied and `supervise` reports `TerminationReason::IdleTimeout`.
+    /// Unless `with_output` was called, the output of the process is forwarded to the current process.
+    /// The timeout is ignored if neither stdout nor stderr was spawned with `Stdio::piped()`, the guard not seeing
+    /// the output of the process.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum duration without output.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use std::time::Duration;
+    /// use proc_guard::{ProcGuard, ProcessTermination, TerminationReason};
+    ///
+    /// let mut command = if cfg!(target_os = "windows") {
+    ///     let mut command = Command::new("timeout");
+    ///     command.args(["/t", "3"]);
+    ///     command
+    /// } else {
+    ///     let mut command = Command::new("sleep");
+    ///     command.arg("3");
+    ///     command
+    /// };
+    ///
+    /// let guard = ProcGuard::spawn(command.stdout(Stdio::piped()), ProcessTermination::KillWait)?
+    ///     .with_idle_timeout(Duration::from_millis(500));
+    /// let outcome = guard.supervise()?;
+    /// assert!(matches!(outcome.reason, TerminationReason::IdleTimeout(_)));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Keeps the most recent output of the process in a bounded ring buffer.
+    ///
+    /// The buffer is attached to the outcomes of `supervise` and `terminate_with_output`,
+    /// and errors returned while terminating the process are wrapped in `Error::WithRecentOutput`.
+    /// Unless `with_output` was called, the output of the process is forwarded to the current process.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - How many lines or bytes of output to keep.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use proc_guard::{OutputMode, ProcGuard, ProcessTermination, RecentOutputLimit};
+    ///
+    /// let mut command = if cfg!(target_os = "windows") {
+    ///     let mut command = Command::new("cmd");
+    ///     command.args(["/C", "echo hello"]);
+    ///     command
+    /// } else {
+    ///     let mut command = Command::new("echo");
+    ///     command.arg("hello");
+    ///     command
+    /// };
+    ///
+    /// let guard = ProcGuard::spawn(command.stdout(Stdio::piped()), ProcessTermination::Wait)?
+    ///     .with_output(OutputMode::Discard)
+    ///     .with_recent_output(RecentOutputLimit::Lines(20));
+    /// let outcome = guard.terminate_with_output()?;
+    /// assert_eq!(outcome.recent_output.expect("No recent output").lines[0].text.trim(), "hello");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_recent_output(mut self, limit: RecentOutputLimit) -> Self {
+        self.recent_output = Some(limit);
+        if let Some(output) = &self.output {
+            output.keep_recent(limit);
+        }
+        self
+    }
+
+    /// Keeps every line read by the guard until it is consumed by `wait_for_line` or `take_unread_lines`.
+    ///
+    /// Without this option, lines are only kept from the first call to one of them, so a line read before is missed.
+    /// Call it before `with_output` so that the lines are kept from the first one read.
+    /// At most the last 10000 unread lines, and 1 MiB of them, are kept.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use std::time::Duration;
+    /// use proc_guard::{OutputMode, ProcGuard, ProcessTermination};
+    ///
+    /// let mut command = if cfg!(target_os = "windows") {
+    ///     let mut command = Command::new("cmd");
+    ///     command.args(["/C", "echo ready && timeout /t 2"]);
+    ///     command
+    /// } else {
+    ///     let mut command = Command::new("sh");
+    ///     command.args(["-c", "echo ready; sleep 2"]);
+    ///     command
+    /// };
+    ///
+    /// let mut guard = ProcGuard::spawn(command.stdout(Stdio::piped()), ProcessTermination::KillWait)?
+    ///     .with_unread_lines()
+    ///     .with_output(OutputMode::Discard);
+    /// std::thread::sleep(Duration::from_millis(500));
+    /// let line = guard.wait_for_line("ready", Duration::from_secs(5))?;
+    /// assert!(line.text.starts_with("ready"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_unread_lines(mut self) -> Self {
+        self.unread_lines = true;
+        if let Some(output) = &self.output {
+            output.keep_pending();
+        }
+        self
     }
 
     /// Returns a reference to the child process.
@@ -186,6 +727,210 @@ impl ProcGuard {
         &mut self.child
     }
 
+    /// Returns whether the process exited, without reaping it on Unix so that the guard still collects its exit status
+    /// and resource usage.
+    pub(crate) fn has_exited(&mut self) -> io::Result<bool> {
+        _has_exited(&mut self.child)
+    }
+
+    /// Reads the piped output of the process until a line matches, without releasing the guard.
+    ///
+    /// Lines of stdout and stderr are consumed in the order they were read, from the first line read by the guard if
+    /// `with_unread_lines` was called or the guard did not own the pipes yet, otherwise from the first call to
+    /// `wait_for_line` or `take_unread_lines`.
+    /// The lines read after the matching one are kept for the next call, or for `take_unread_lines`.
+    /// Unless the guard already owns the pipes, such as after `with_output`, it starts owning them and forwards the
+    /// output to the current process.
+    /// At most the last 10000 unread lines, and 1 MiB of them, are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `matcher` - A `&str` or `String` that the line must contain, or a closure taking the line.
+    /// * `timeout` - The maximum duration to wait for a matching line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(OutputLine)` - The matching line.
+    /// * `Err(Error::LineNotFound)` - With the lines consumed, if the timeout elapsed or the output ended before a line matched.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use std::time::Duration;
+    /// use proc_guard::{OutputMode, ProcGuard, ProcessTermination};
+    ///
+    /// let mut command = if cfg!(target_os = "windows") {
+    ///     let mut command = Command::new("cmd");
+    ///     command.args(["/C", "echo listening on 8080 && timeout /t 2"]);
+    ///     command
+    /// } else {
+    ///     let mut command = Command::new("sh");
+    ///     command.args(["-c", "echo listening on 8080; sleep 2"]);
+    ///     command
+    /// };
+    ///
+    /// let mut guard = ProcGuard::spawn(command.stdout(Stdio::piped()), ProcessTermination::KillWait)?
+    ///     .with_output(OutputMode::Discard);
+    /// let line = guard.wait_for_line("listening on", Duration::from_secs(5))?;
+    /// assert!(line.text.starts_with("listening on 8080"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn wait_for_line<M: LineMatcher>(
+        &mut self,
+        mut matcher: M,
+        timeout: Duration,
+    ) -> Result<OutputLine, Error> {
+        self.unread_lines = true;
+        self._output(OutputMode::Forward)
+            .wait_for_line(&mut matcher, timeout)
+            .map_err(|lines| Error::LineNotFound(RecentOutput { lines }))
+    }
+
+    /// Consumes the lines read by the guard that were not consumed by `wait_for_line` yet.
+    ///
+    /// The lines are kept from the first call to `wait_for_line` or `take_unread_lines`, unless `with_unread_lines`
+    /// was called.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<OutputLine>` - The unread lines, empty if the guard does not own the pipes of the process.
+    pub fn take_unread_lines(&mut self) -> Vec<OutputLine> {
+        self.unread_lines = true;
+        match &self.output {
+            Some(output) => output.take_pending_lines(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Reads the niceness, CPU affinity, OOM score adjustment and I/O priority of the running process, such as the ones
+    /// set by `SpawnOptions::scheduling`.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If the process exited and was reaped, or a setting could not be read.
+    #[cfg(target_os = "linux")]
+    pub fn scheduling(&self) -> Result<SchedulingInfo, Error> {
+        Ok(SchedulingInfo::read(self.child.id())?)
+    }
+
+    /// Returns the master side of the pseudo-terminal of a process spawned with `SpawnOptions::pty`.
+    ///
+    /// Reading it returns the output of the process, writing to it is like typing in the terminal.
+    /// Once the process and its descendants closed the terminal, reading it fails with an I/O error.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&File)` - The master side of the pseudo-terminal, `&File` implements both `Read` and `Write`.
+    /// * `None` - If the process was not spawned in a pseudo-terminal.
+    #[cfg(unix)]
+    pub fn pty_master(&self) -> Option<&File> {
+        self.pty_master.as_ref()
+    }
+
+    /// Sends a Ctrl+C signal to the process, the same way the termination strategy does.
+    ///
+    /// The process is resumed first if it is paused or stopped.
+    /// For a process spawned in a pseudo-terminal, the Ctrl+C character is written to the terminal.
+    /// Otherwise this is `proc_guard::send_ctrl_c`.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::FailedToSendCtrlC` - with a system specific error code if the internal OS API failed.
+    /// * `proc_guard::Error::PermissionDenied` - On Unix, if the current process is not allowed to signal the process.
+    pub fn send_ctrl_c(&mut self) -> Result<(), Error> {
+        let mut actions = Actions {
+            pty_master: self.pty_master.as_ref(),
+            #[cfg(unix)]
+            paused: self.paused.take(),
+            ..Actions::default()
+        };
+        let result = actions
+            .resume(&mut self.child)
+            .and_then(|_| actions.ctrl_c(&mut self.child));
+        #[cfg(unix)]
+        {
+            self.paused = actions.paused;
+        }
+        result
+    }
+
+    /// Pauses the process until `resume` is called, to throttle it or to freeze it while its state is snapshotted.
+    ///
+    /// On Linux, if the process has its own cgroup v2, holding nothing but the process and its descendants, the
+    /// cgroup is frozen, which also pauses the descendants. Otherwise the process is sent `SIGSTOP`, so that a cgroup
+    /// shared with other processes, such as a systemd slice, is never frozen.
+    /// Pausing a paused process does nothing.
+    ///
+    /// The termination plan resumes the process before any step but a kill, a stopped process never handling the
+    /// signals asking it to exit gracefully. A frozen cgroup is thawed once the process is killed, and when the guard
+    /// is dropped, so that the descendants of the process do not stay frozen.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PauseMethod)` - How the process was paused.
+    /// * `Err(Error)` - If the process exited or could not be paused.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::Command;
+    /// use proc_guard::{ProcGuard, ProcessTermination};
+    ///
+    /// let mut guard = ProcGuard::spawn(Command::new("sleep").arg("2"), ProcessTermination::KillWait)?;
+    /// guard.pause()?;
+    /// assert!(guard.paused().is_some());
+    /// guard.resume()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn pause(&mut self) -> Result<PauseMethod, Error> {
+        if let Some(method) = &self.paused {
+            return Ok(method.clone());
+        }
+        // The pid of a reaped process may be reused
+        if _has_exited(&mut self.child)? {
+            return Err(io::Error::from_raw_os_error(libc::ESRCH).into());
+        }
+
+        let method = pause::pause(&self.child)?;
+        self.paused = Some(method.clone());
+        Ok(method)
+    }
+
+    /// Resumes the process paused by `pause`.
+    ///
+    /// On Linux, a process stopped by someone else, for instance by a `SIGSTOP`, is resumed with `SIGCONT`.
+    /// Otherwise resuming a process that is not paused does nothing.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If the process could not be resumed.
+    #[cfg(unix)]
+    pub fn resume(&mut self) -> Result<(), Error> {
+        let mut actions = Actions {
+            paused: self.paused.take(),
+            ..Actions::default()
+        };
+        let result = actions.resume(&mut self.child);
+        self.paused = actions.paused;
+        result
+    }
+
+    /// Returns how the process was paused, `None` if it is not paused by `pause`.
+    #[cfg(unix)]
+    pub fn paused(&self) -> Option<&PauseMethod> {
+        self.paused.as_ref()
+    }
+
     /// Releases the guard without terminating the process.
     ///
     /// # Returns
@@ -214,6 +959,10 @@ impl ProcGuard {
     /// ```
     pub fn release(mut self) -> Child {
         self.dropped = true;
+        #[cfg(unix)]
+        {
+            self.forwarding = None;
+        }
         unsafe { ManuallyDrop::take(&mut self.child) }
     }
 
@@ -228,8 +977,109 @@ impl ProcGuard {
             return Ok(None);
         }
         self.dropped = true;
+        // The process is reaped below, its pid may be reused afterward
+        #[cfg(unix)]
+        {
+            self.forwarding = None;
+        }
+        self._start_output();
+
+        let mut actions = Actions {
+            pty_master: self.pty_master.as_ref(),
+            output: self.output.as_ref(),
+            #[cfg(target_os = "linux")]
+            kill_snapshot: self.kill_snapshot.as_ref(),
+            #[cfg(unix)]
+            paused: self.paused.take(),
+            #[cfg(target_os = "linux")]
+            subreaper: self.subreaper.as_ref(),
+            #[cfg(target_os = "linux")]
+            tree: self.tree_kill.map(TreeKill::new),
+            #[cfg(unix)]
+            collect_usage: true,
+            ..Actions::default()
+        };
+        let result = _terminate(&mut self.child, &self.termination, &mut actions);
+        self.ended_at = Some(Instant::now());
+        // Nothing can resume the process once the guard is dropped, the cgroup may already be removed
+        #[cfg(target_os = "linux")]
+        let _ = actions.thaw(&self.child);
+        #[cfg(unix)]
+        {
+            self.usage = actions.usage;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(subreaper) = &self.subreaper {
+            // Killed orphans exit promptly, the others are only reaped if they already exited
+            let killed = actions
+                .escalations
+                .iter()
+                .any(|escalation| escalation.step == EscalationStep::Kill);
+            self.orphans = subreaper.collect(killed);
+        }
+        #[cfg(unix)]
+        {
+            self.diagnostics = actions.diagnostics;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            self.snapshots = actions.snapshots;
+        }
+        self.escalations = actions.escalations;
+        #[cfg(unix)]
+        {
+            self.paused = actions.paused;
+        }
+
+        // The last lines of the process are routed before the guard is released
+        if let Some(output) = self.output.as_ref().filter(|output| output.is_routed()) {
+            output.settle(OUTPUT_GRACE);
+        }
+        result
+    }
+
+    /// Starts reading the output of the process if an option requires it and `with_output` was not called.
+    fn _start_output(&mut self) {
+        #[cfg(unix)]
+        let dumps = self.termination.dumps();
+        #[cfg(not(unix))]
+        let dumps = false;
+
+        if self.idle_timeout.is_some() || self.recent_output.is_some() || dumps {
+            self._output(OutputMode::Forward);
+        }
+    }
 
-        terminate(&mut self.child, self.termination)
+    /// Returns the pump reading the output of the process, starting it with `mode` if it was not started yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - What to do with the output if the pump is started.
+    fn _output(&mut self, mode: OutputMode) -> &OutputPump {
+        if self.output.is_none() {
+            self._start(OutputPump::new(mode));
+        }
+
+        self.output.as_ref().expect("The output pump is started")
+    }
+
+    /// Starts a pump reading the output of the process, keeping the recent output and unread lines if configured.
+    fn _start(&mut self, output: OutputPump) {
+        if let Some(limit) = self.recent_output {
+            output.keep_recent(limit);
+        }
+        if self.unread_lines {
+            output.keep_pending();
+        }
+        self.output = Some(output.start(&mut self.child));
+    }
+
+    /// Wraps an error with the most recent output of the process, if it is kept.
+    fn _attach_recent_output(&self, error: Error) -> Error {
+        match self.output.as_ref().and_then(OutputPump::recent) {
+            Some(recent) => Error::WithRecentOutput(Box::new(error), recent),
+            None => error,
+        }
     }
 
     /// Terminates the process according to the specified termination strategy and releases the guard.
@@ -262,25 +1112,239 @@ impl ProcGuard {
     /// ```
     pub fn terminate(mut self) -> Result<Option<ExitStatus>, Error> {
         self._drop_impl()
+            .map_err(|error| self._attach_recent_output(error))
     }
-}
 
-/// Wait for a specified duration, then kill the process if it hasn't exited.
-///
-/// # Returns
-///
-/// * `Ok(Some(ExitStatus))` - If the wait did not timeout.
-/// * `Ok(None)` - If the wait timeouted and kill was called.
-/// * `Err(Error)` - If an error occurs during termination.
-///
-fn _wait_timeout_kill(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>, Error> {
-    match child.wait_timeout(timeout) {
-        Ok(v) => Ok(Some(v)),
-        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
-            child.kill()?;
-            Ok(None)
+    /// Terminates the process according to the specified termination strategy, releases the guard and returns the output captured with `OutputMode::Capture`.
+    ///
+    /// This is the guarded counterpart of `Child::wait_with_output`, with the timeouts of the termination strategy.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TerminationOutcome)` - The exit status as returned by `terminate` and the captured output, if any.
+    /// * `Err(Error)` - If an error occurs during termination.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use proc_guard::{OutputMode, ProcGuard, ProcessTermination};
+    ///
+    /// let mut command = if cfg!(target_os = "windows") {
+    ///     let mut command = Command::new("cmd");
+    ///     command.args(["/C", "echo hello"]);
+    ///     command
+    /// } else {
+    ///     let mut command = Command::new("echo");
+    ///     command.arg("hello");
+    ///     command
+    /// };
+    ///
+    /// let guard = ProcGuard::spawn(command.stdout(Stdio::piped()), ProcessTermination::Wait)?
+    ///     .with_output(OutputMode::Capture);
+    /// let outcome = guard.terminate_with_output()?;
+    /// assert!(outcome.status.expect("No exit status").success());
+    /// assert!(outcome.output.expect("No captured output").stdout.starts_with(b"hello"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn terminate_with_output(mut self) -> Result<TerminationOutcome, Error> {
+        match self._drop_impl() {
+            Ok(status) => Ok(self._outcome(status, TerminationReason::Requested)),
+            Err(error) => Err(self._attach_recent_output(error)),
+        }
+    }
+
+    /// Waits for the process to exit on its own while enforcing the guard's watchdogs, such as resource limits.
+    /// If a watchdog fires, the termination strategy is applied and the guard is released.
+    /// The watchdogs, `with_resource_limits` and `with_idle_timeout`, are not enforced outside of this method.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TerminationOutcome)` - How the process ended, `reason` tells whether it exited on its own or which watchdog fired.
+    /// * `Err(Error)` - If an error occurs while waiting for the process or during termination.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use proc_guard::{ProcGuard, ProcessTermination, TerminationReason};
+    ///
+    /// let child = if cfg!(target_os = "windows") {
+    ///     Command::new("timeout").args(["/t", "1"]).spawn()?
+    /// } else {
+    ///     Command::new("sleep").arg("1").spawn()?
+    /// };
+    ///
+    /// let guard = ProcGuard::new(child, ProcessTermination::KillWait);
+    /// let outcome = guard.supervise()?;
+    /// assert!(matches!(outcome.reason, TerminationReason::Exited));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn supervise(mut self) -> Result<TerminationOutcome, Error> {
+        self._supervise()
+            .map_err(|error| self._attach_recent_output(error))
+    }
+
+    /// Implementation of `supervise`, errors are not wrapped with the recent output yet.
+    fn _supervise(&mut self) -> Result<TerminationOutcome, Error> {
+        self._start_output();
+
+        let interval = match self._watchdog_interval() {
+            Some(interval) => interval,
+            None => {
+                let status = self._wait(true)?.expect("A blocking wait returns a status");
+                return Ok(self._exited(status));
+            }
+        };
+
+        loop {
+            if let Some(status) = self._wait(false)? {
+                return Ok(self._exited(status));
+            }
+
+            if let Some(reason) = self._check_watchdogs()? {
+                let status = self._drop_impl()?;
+                return Ok(self._outcome(status, reason));
+            }
+
+            thread::sleep(interval);
         }
-        Err(e) => Err(e.into()),
+    }
+
+    /// Waits for the process to exit on its own, collecting its resource usage on Unix.
+    ///
+    /// The signals stop being forwarded to the process before it is reaped.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(ExitStatus))` - If the process exited.
+    /// * `Ok(None)` - If the process is still running and `block` is false.
+    /// * `Err(io::Error)` - If waiting for the process failed.
+    fn _wait(&mut self, block: bool) -> io::Result<Option<ExitStatus>> {
+        #[cfg(unix)]
+        {
+            let timeout = if block { None } else { Some(Duration::ZERO) };
+            if !usage::wait_exited(&mut self.child, timeout)? {
+                return Ok(None);
+            }
+            // The pid may be reused once the process is reaped, the signals must not be forwarded to it anymore
+            self.forwarding = None;
+            let reaped = usage::reap(&mut self.child)?;
+            Ok(reaped.map(|reaped| {
+                self.usage = reaped.usage;
+                reaped.status
+            }))
+        }
+        #[cfg(not(unix))]
+        if block {
+            self.child.wait().map(Some)
+        } else {
+            self.child.try_wait()
+        }
+    }
+
+    /// Releases the guard of a process that exited on its own.
+    fn _exited(&mut self, status: ExitStatus) -> TerminationOutcome {
+        self.dropped = true;
+        self.ended_at = Some(Instant::now());
+        #[cfg(target_os = "linux")]
+        if let Some(subreaper) = &self.subreaper {
+            self.orphans = subreaper.collect(false);
+        }
+        self._outcome(Some(status), TerminationReason::Exited)
+    }
+
+    /// Builds the outcome of a process that ended, collecting its captured and recent output.
+    fn _outcome(
+        &self,
+        status: Option<ExitStatus>,
+        reason: TerminationReason,
+    ) -> TerminationOutcome {
+        let output = self
+            .output
+            .as_ref()
+            .and_then(|output| output.finish(OUTPUT_GRACE));
+
+        TerminationOutcome {
+            status,
+            exit: status.map(|status| ExitInfo::attribute(status, &self.escalations)),
+            reason,
+            output,
+            recent_output: self.output.as_ref().and_then(OutputPump::recent),
+            #[cfg(unix)]
+            diagnostics: self.diagnostics.clone(),
+            #[cfg(target_os = "linux")]
+            snapshots: self.snapshots.clone(),
+            #[cfg(target_os = "linux")]
+            orphans: self.orphans.clone(),
+            lifetime: self
+                .ended_at
+                .unwrap_or_else(Instant::now)
+                .duration_since(self.spawned_at),
+            #[cfg(unix)]
+            usage: self.usage,
+        }
+    }
+
+    /// Returns the idle-output timeout, `None` if it is not set or if no stream of the process is read.
+    fn _idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+            .filter(|_| self.output.as_ref().is_some_and(OutputPump::reads_output))
+    }
+
+    /// Returns the interval at which the watchdogs must be checked, `None` if there is no watchdog.
+    fn _watchdog_interval(&self) -> Option<Duration> {
+        let idle = self
+            ._idle_timeout()
+            .map(|timeout| timeout.min(IDLE_CHECK_INTERVAL));
+
+        #[cfg(target_os = "linux")]
+        let limits = self
+            .resource_limits
+            .map(|limits| limits.sampling_interval());
+        #[cfg(not(target_os = "linux"))]
+        let limits = None;
+
+        [idle, limits].into_iter().flatten().min()
+    }
+
+    /// Checks every watchdog once.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(TerminationReason))` - If a watchdog fired.
+    /// * `Ok(None)` - If no watchdog fired.
+    /// * `Err(Error)` - If a watchdog could not be checked.
+    fn _check_watchdogs(&mut self) -> Result<Option<TerminationReason>, Error> {
+        if let (Some(timeout), Some(output)) = (self._idle_timeout(), &self.output) {
+            if output.idle_for() >= timeout {
+                return Ok(Some(TerminationReason::IdleTimeout(timeout)));
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(limits) = &self.resource_limits {
+            match sample_resources(self.child.id()) {
+                Ok(sample) => {
+                    if let Some(exceeded) = limits.check(&sample) {
+                        return Ok(Some(TerminationReason::LimitExceeded(exceeded)));
+                    }
+                }
+                // The process exited after the last wait, the next one reports it
+                Err(_) if _has_exited(&mut self.child)? => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(None)
     }
 }
 
@@ -289,7 +1353,7 @@ fn _wait_timeout_kill(child: &mut Child, timeout: Duration) -> Result<Option<Exi
 /// # Arguments
 ///
 /// * `child` - The child process to terminate.
-/// * `termination` - The termination strategy to use.
+/// * `termination` - The termination strategy to use, a `ProcessTermination` or a `TerminationPlan`.
 ///
 /// # Returns
 ///
@@ -303,7 +1367,13 @@ fn _wait_timeout_kill(child: &mut Child, timeout: Duration) -> Result<Option<Exi
 ///
 /// * `proc_guard::Error::FailedToSendCtrlC` - with a system specific error code if the internal OS API failed.
 ///
+/// * `proc_guard::Error::PermissionDenied` - If the current process is not allowed to signal the process, such as a process running as another user.
+///
+/// * `proc_guard::Error::StdinNotPiped` - If the strategy closes or writes to the stdin of a process that was not spawned with `Stdio::piped()`.
+///
 /// * `proc_guard::Error::Timeout` - If the specified timeout duration elapses before the process completes AND the termination procedure did not attempt to kill the process afterward.
+///   Strategies such as `ProcessTermination::WaitTimeout` used to fail with an `Error::Io` of kind `TimedOut` in
+///   this case, they fail with `Error::Timeout` since they run as termination plans.
 ///
 /// * `proc_guard::Error::WaitFailed` - with a system specific error code if the internal OS API failed.
 ///
@@ -330,36 +1400,313 @@ fn _wait_timeout_kill(child: &mut Child, timeout: Duration) -> Result<Option<Exi
 /// ```
 pub fn terminate(
     child: &mut Child,
-    termination: ProcessTermination,
+    termination: impl Into<TerminationPlan>,
+) -> Result<Option<ExitStatus>, Error> {
+    _terminate(child, &termination.into(), &mut Actions::default())
+}
+
+/// Performs the actions of the termination steps, through the pseudo-terminal of the process if it has one.
+#[derive(Debug, Default)]
+struct Actions<'a> {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pty_master: Option<&'a File>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    output: Option<&'a OutputPump>,
+    /// The diagnostics recorded by the dump steps.
+    #[cfg(unix)]
+    diagnostics: Vec<Diagnostics>,
+    #[cfg(target_os = "linux")]
+    kill_snapshot: Option<&'a KillSnapshot>,
+    /// The snapshots taken before the kill steps.
+    #[cfg(target_os = "linux")]
+    snapshots: Vec<ProcSnapshot>,
+    /// The steps sent so far that may end the process.
+    escalations: Vec<Escalation>,
+    /// How the process was paused by `ProcGuard::pause`.
+    #[cfg(unix)]
+    paused: Option<PauseMethod>,
+    /// The subreaper sending the Ctrl+C and kill steps to the orphans of the process too.
+    #[cfg(target_os = "linux")]
+    subreaper: Option<&'a Tracking>,
+    /// The descendants sent the Ctrl+C and kill steps too.
+    #[cfg(target_os = "linux")]
+    tree: Option<TreeKill>,
+    /// Whether the waits reap the process with `wait4`, which the `Child` does not know about.
+    #[cfg(unix)]
+    collect_usage: bool,
+    /// The resources used by the process, once reaped with `collect_usage`.
+    #[cfg(unix)]
+    usage: Option<ResourceUsage>,
+}
+
+impl Actions<'_> {
+    /// Records a step that may end the process, `start` being the start of the termination.
+    fn escalate(&mut self, step: EscalationStep, start: Instant) {
+        self.escalations.push(Escalation {
+            step,
+            after: start.elapsed(),
+        });
+    }
+
+    /// Kills the process, taking a snapshot of its process tree first if it is still running and a snapshot is requested.
+    /// A cgroup frozen by `ProcGuard::pause` is thawed afterward.
+    fn kill(&mut self, child: &mut Child) -> Result<(), Error> {
+        self._kill(child)?;
+        #[cfg(target_os = "linux")]
+        self.thaw(child)?;
+        Ok(())
+    }
+
+    /// Kills the process, see `kill`.
+    fn _kill(&mut self, child: &mut Child) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        if let Some(kill_snapshot) = self.kill_snapshot {
+            if !_has_exited(child)? {
+                self.snapshots.push(kill_snapshot.take(child.id()));
+            }
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(subreaper) = self.subreaper {
+            subreaper.signal_orphans(libc::SIGKILL);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(tree) = &mut self.tree {
+            let pid = child.id();
+            return tree.signal(pid, libc::SIGKILL, || {
+                child.kill().map_err(|e| Error::from_signal(e, pid))
+            });
+        }
+
+        let pid = child.id();
+        child.kill().map_err(|e| Error::from_signal(e, pid))
+    }
+
+    /// Waits for the process to exit, without timeout if `timeout` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// * `io::ErrorKind::TimedOut` - If `timeout` elapsed before the process exited.
+    fn wait(&mut self, child: &mut Child, timeout: Option<Duration>) -> io::Result<ExitStatus> {
+        #[cfg(unix)]
+        if self.collect_usage {
+            return match usage::wait(child, timeout)? {
+                Some(reaped) => {
+                    self.usage = reaped.usage;
+                    Ok(reaped.status)
+                }
+                None => Err(io::ErrorKind::TimedOut.into()),
+            };
+        }
+
+        match timeout {
+            Some(timeout) => child.wait_timeout(timeout),
+            None => child.wait(),
+        }
+    }
+
+    /// Resumes the process if it is paused or stopped, so that it can handle the following steps.
+    fn resume(&mut self, child: &mut Child) -> Result<(), Error> {
+        #[cfg(unix)]
+        {
+            // The pid of a reaped process may be reused, it must not be signaled
+            if _has_exited(child)? {
+                #[cfg(target_os = "linux")]
+                self.thaw(child)?;
+                self.paused = None;
+                return Ok(());
+            }
+            if let Some(method) = &self.paused {
+                pause::resume(child, method)?;
+                self.paused = None;
+            }
+            pause::resume_stopped(child)?;
+        }
+        #[cfg(not(unix))]
+        let _ = child;
+
+        Ok(())
+    }
+
+    /// Thaws the cgroup frozen by `ProcGuard::pause`, without signaling the process.
+    #[cfg(target_os = "linux")]
+    fn thaw(&mut self, child: &Child) -> Result<(), Error> {
+        if let Some(method @ PauseMethod::CgroupFreeze(_)) = &self.paused {
+            pause::resume(child, method)?;
+            self.paused = None;
+        }
+        Ok(())
+    }
+
+    /// Sends a Ctrl+C signal to the process.
+    fn ctrl_c(&mut self, child: &mut Child) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        if let Some(subreaper) = self.subreaper {
+            subreaper.signal_orphans(libc::SIGINT);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(mut tree) = self.tree.take() {
+            let result = tree.signal(child.id(), libc::SIGINT, || self._ctrl_c(child));
+            self.tree = Some(tree);
+            return result;
+        }
+
+        self._ctrl_c(child)
+    }
+
+    /// Sends a Ctrl+C signal to the process only.
+    fn _ctrl_c(&self, child: &mut Child) -> Result<(), Error> {
+        #[cfg(unix)]
+        if let Some(master) = self.pty_master {
+            return pty::send_ctrl_c(master);
+        }
+
+        send_ctrl_c(child)
+    }
+
+    /// Closes the stdin of the process. In a pseudo-terminal, the end-of-file character is sent instead.
+    fn close_stdin(&self, child: &mut Child) -> Result<(), Error> {
+        #[cfg(unix)]
+        if let Some(master) = self.pty_master {
+            return pty::send_eof(master);
+        }
+
+        match child.stdin.take() {
+            Some(stdin) => {
+                drop(stdin);
+                Ok(())
+            }
+            None => _exited_or_stdin_not_piped(child),
+        }
+    }
+
+    /// Writes `input` to the stdin of the process.
+    fn write_stdin(&self, child: &mut Child, input: &[u8]) -> Result<(), Error> {
+        #[cfg(unix)]
+        if let Some(mut master) = self.pty_master {
+            return Ok(master.write_all(input)?);
+        }
+
+        let stdin = match child.stdin.as_mut() {
+            Some(stdin) => stdin,
+            None => return _exited_or_stdin_not_piped(child),
+        };
+        match stdin.write_all(input).and_then(|_| stdin.flush()) {
+            // The process closed its stdin, the following steps tell whether it exited
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => Ok(result?),
+        }
+    }
+}
+
+/// Checks that a process without stdin exited, `wait` closing the stdin of the process.
+fn _exited_or_stdin_not_piped(child: &mut Child) -> Result<(), Error> {
+    match _has_exited(child)? {
+        true => Ok(()),
+        false => Err(Error::StdinNotPiped),
+    }
+}
+
+/// Returns whether the process exited, without reaping it on Unix so that its resource usage can be collected.
+fn _has_exited(child: &mut Child) -> io::Result<bool> {
+    #[cfg(unix)]
+    return usage::exited(child);
+    #[cfg(not(unix))]
+    Ok(child.try_wait()?.is_some())
+}
+
+/// Runs a user-defined step on its own thread, giving up on it once `timeout` elapsed.
+///
+/// # Returns
+///
+/// * `Err(Error::Timeout)` - If the step did not return in time, it keeps running in the background.
+/// * Otherwise the result of the step.
+fn _run_bounded_step(
+    step: &Arc<dyn TerminationStep>,
+    pid: u32,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let deadline = Instant::now() + timeout;
+    let (sender, receiver) = mpsc::channel();
+    let step = step.clone();
+    let handle = thread::spawn(move || {
+        let _ = sender.send(step.run(pid, Some(deadline)));
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+        // The step panicked, the panic is propagated as if the step ran on the current thread
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(panic) => panic::resume_unwind(panic),
+            Ok(()) => Err(Error::UnknownError()),
+        },
+    }
+}
+
+/// Implementation of `terminate` where the actions are performed by `actions`.
+fn _terminate(
+    child: &mut Child,
+    plan: &TerminationPlan,
+    actions: &mut Actions,
 ) -> Result<Option<ExitStatus>, Error> {
-    match termination {
-        ProcessTermination::Wait => Ok(Some(child.wait()?)),
-        ProcessTermination::WaitTimeout(timeout) => Ok(Some(child.wait_timeout(timeout)?)),
-        ProcessTermination::WaitTimeoutKill(timeout) => _wait_timeout_kill(child, timeout),
-        ProcessTermination::CtrlC => {
-            send_ctrl_c(child)?;
-            Ok(None)
-        }
-        ProcessTermination::CtrlCWait => {
-            send_ctrl_c(child)?;
-            Ok(Some(child.wait()?))
-        }
-        ProcessTermination::CtrlCWaitTimeout(timeout) => {
-            send_ctrl_c(child)?;
-            Ok(Some(child.wait_timeout(timeout)?))
-        }
-        ProcessTermination::CtrlCWaitTimeoutKill(timeout) => {
-            send_ctrl_c(child)?;
-            _wait_timeout_kill(child, timeout)
-        }
-        ProcessTermination::Kill => {
-            child.kill()?;
-            Ok(None)
-        }
-        ProcessTermination::KillWait => {
-            child.kill()?;
-            Ok(Some(child.wait()?))
+    let start = Instant::now();
+    let mut timed_out = false;
+
+    for step in &plan.steps {
+        timed_out = false;
+        // A stopped process never handles the steps asking it to exit, a kill does not need it to run
+        if !matches!(step, Step::Kill) {
+            actions.resume(child)?;
         }
+        match step {
+            Step::CtrlC => {
+                actions.escalate(EscalationStep::CtrlC, start);
+                actions.ctrl_c(child)?
+            }
+            Step::Kill => {
+                actions.escalate(EscalationStep::Kill, start);
+                actions.kill(child)?
+            }
+            Step::CloseStdin => actions.close_stdin(child)?,
+            Step::WriteStdin(input) => actions.write_stdin(child, input)?,
+            Step::Wait => return Ok(Some(actions.wait(child, None)?)),
+            Step::WaitTimeout(timeout) => match actions.wait(child, Some(*timeout)) {
+                Ok(status) => return Ok(Some(status)),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => timed_out = true,
+                Err(e) => return Err(e.into()),
+            },
+            Step::Custom(step, timeout) => {
+                let result = match timeout {
+                    Some(timeout) => _run_bounded_step(step, child.id(), *timeout),
+                    None => step.run(child.id(), None),
+                };
+                match result {
+                    Ok(()) => {}
+                    Err(Error::Timeout) => timed_out = true,
+                    Err(e) => return Err(e),
+                }
+            }
+            #[cfg(unix)]
+            Step::Dump(dump) => {
+                actions.escalate(EscalationStep::Signal(dump.signal_number()), start);
+                let output = actions.output;
+                let (diagnostics, status) =
+                    dump::run_dump(child, dump, output, |child, window| {
+                        actions.wait(child, Some(window))
+                    })?;
+                actions.diagnostics.push(diagnostics);
+                if status.is_some() {
+                    return Ok(status);
+                }
+            }
+        }
+    }
+
+    // The last step timed out, nothing attempted to terminate the process afterward
+    if timed_out {
+        Err(Error::Timeout)
+    } else {
+        Ok(None)
     }
 }
 
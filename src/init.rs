@@ -0,0 +1,190 @@
+use std::{
+    io, mem,
+    process::{Command, ExitStatus},
+    thread,
+    time::Duration,
+};
+
+use libc::{c_int, SIGHUP, SIGINT, SIGQUIT, SIGTERM, SIGUSR1, SIGUSR2, SIGWINCH};
+
+use crate::{
+    error::Error,
+    exit::ExitInfo,
+    forward::SignalForwarder,
+    guard::{ProcGuard, ProcessTermination},
+    plan::TerminationPlan,
+    shutdown::ShutdownSignals,
+};
+
+/// Interval at which the init reaps orphans and checks whether the main process exited or a shutdown was requested.
+const INIT_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Grace period given to the main process by the default termination plan of the init.
+const DEFAULT_INIT_GRACE: Duration = Duration::from_secs(10);
+
+/// Minimal init for containers whose PID 1 is the current process, like `tini` or `dumb-init`.
+///
+/// The init spawns the main process under a `ProcGuard`, forwards the signals it receives to it, and reaps every
+/// orphaned process reparented to it, so that zombies never pile up. A shutdown signal, `SIGTERM` by default, applies
+/// the termination plan to the main process instead of being ignored, PID 1 having no default signal handlers.
+/// The init returns once the main process exited.
+///
+/// The init reaps every child of the current process but the main process, the current process must not spawn
+/// other children while it runs.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use std::time::Duration;
+/// use proc_guard::{InitMode, ProcessTermination};
+///
+/// let mut args = std::env::args_os().skip(1);
+/// let mut command = Command::new(args.next().expect("No command"));
+/// command.args(args);
+///
+/// let outcome = InitMode::new(ProcessTermination::CtrlCWaitTimeoutKill(Duration::from_secs(5))).run(&mut command)?;
+/// std::process::exit(outcome.exit_code);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InitMode {
+    termination: TerminationPlan,
+    forwarded: Vec<c_int>,
+    shutdown: Vec<c_int>,
+}
+
+/// How the main process of an init ended.
+#[derive(Debug)]
+pub struct InitOutcome {
+    /// The exit code the init should exit with, the shell code of the main process, 1 if its exit is unknown.
+    pub exit_code: i32,
+    /// How the main process ended, `None` if the termination plan did not wait for it.
+    pub exit: Option<ExitInfo>,
+    /// Whether the main process was terminated because the init received a shutdown signal.
+    pub shutdown: bool,
+    /// The orphans reaped by the init, with their exit statuses.
+    pub reaped: Vec<(u32, ExitStatus)>,
+}
+
+impl InitMode {
+    /// Creates an init applying `termination` to the main process on shutdown.
+    ///
+    /// By default `SIGTERM` shuts the main process down, while `SIGINT`, `SIGHUP`, `SIGQUIT`, `SIGWINCH`, `SIGUSR1`
+    /// and `SIGUSR2` are forwarded to it.
+    pub fn new(termination: impl Into<TerminationPlan>) -> Self {
+        InitMode {
+            termination: termination.into(),
+            forwarded: vec![SIGINT, SIGHUP, SIGQUIT, SIGWINCH, SIGUSR1, SIGUSR2],
+            shutdown: vec![SIGTERM],
+        }
+    }
+
+    /// Sets the signals forwarded to the main process.
+    pub fn forward(mut self, signals: &[c_int]) -> Self {
+        self.forwarded = signals.to_vec();
+        self
+    }
+
+    /// Sets the signals applying the termination plan to the main process.
+    /// A signal both forwarded and shutting down is only shutting down.
+    pub fn shutdown_signals(mut self, signals: &[c_int]) -> Self {
+        self.shutdown = signals.to_vec();
+        self
+    }
+
+    /// Spawns the main process and runs the init until it exits.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command of the main process.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If the main process cannot be spawned, a signal forwarder is already installed,
+    ///   or waiting for the processes failed.
+    pub fn run(self, command: &mut Command) -> Result<InitOutcome, Error> {
+        let forwarded: Vec<c_int> = self
+            .forwarded
+            .iter()
+            .copied()
+            .filter(|signal| !self.shutdown.contains(signal))
+            .collect();
+        let forwarder = SignalForwarder::install(&forwarded)?;
+        let signals = ShutdownSignals::install(&self.shutdown);
+
+        let guard = ProcGuard::spawn(command, self.termination)?.with_signal_forwarder(&forwarder);
+        let main = guard.child().id();
+
+        let mut reaped = Vec::new();
+        let mut shutdown = false;
+        let outcome = loop {
+            if reap_orphans(main, &mut reaped)? {
+                // The main process exited, the guard reaps it
+                break guard.supervise()?;
+            }
+            if signals.received() {
+                shutdown = true;
+                break guard.terminate_with_output()?;
+            }
+            thread::sleep(INIT_CHECK_INTERVAL);
+        };
+
+        // The orphans that exited with the main process
+        reap_orphans(main, &mut reaped)?;
+        drop(signals);
+        forwarder.uninstall()?;
+
+        Ok(InitOutcome {
+            exit_code: outcome.exit.as_ref().map_or(1, ExitInfo::shell_code),
+            exit: outcome.exit,
+            shutdown,
+            reaped,
+        })
+    }
+}
+
+impl Default for InitMode {
+    fn default() -> Self {
+        Self::new(ProcessTermination::CtrlCWaitTimeoutKill(DEFAULT_INIT_GRACE))
+    }
+}
+
+/// Reaps the exited children of the current process but `spared`, which is left for its guard to reap.
+///
+/// # Returns
+///
+/// * `Ok(true)` - If `spared` exited, the children that exited after it being left for the next call.
+/// * `Ok(false)` - If `spared` is still running.
+/// * `Err(io::Error)` - If waiting for the children failed.
+pub(crate) fn reap_orphans(spared: u32, reaped: &mut Vec<(u32, ExitStatus)>) -> io::Result<bool> {
+    use std::os::unix::process::ExitStatusExt;
+
+    loop {
+        // Peek at an exited child without reaping it, `spared` must keep its status for its guard
+        let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+        let flags = libc::WEXITED | libc::WNOHANG | libc::WNOWAIT;
+        if unsafe { libc::waitid(libc::P_ALL, 0, &mut info, flags) } != 0 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() == Some(libc::ECHILD) {
+                return Ok(false);
+            }
+            return Err(error);
+        }
+
+        let pid = unsafe { info.si_pid() };
+        if pid == 0 {
+            return Ok(false);
+        }
+        if pid as u32 == spared {
+            return Ok(true);
+        }
+
+        let mut status = 0;
+        if unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } == pid {
+            reaped.push((pid as u32, ExitStatus::from_raw(status)));
+        }
+    }
+}
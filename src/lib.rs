@@ -6,6 +6,26 @@
 //!
 //! - **Process Guarding**: Safely manage the lifecycle of child processes with the `ProcGuard` struct, ensuring they are terminated according to specified strategies.
 //! - **Termination Strategies**: Utilize the `ProcessTermination` enum to define various termination strategies, including waiting, timeouts, Ctrl+C signals, and immediate termination.
+//! - **Resource Monitoring**: On Linux, supervise a process and apply its termination strategy when it exceeds a resident memory, CPU time, file descriptor or thread limit, the limits being checked while `supervise` runs.
+//! - **Output Supervision**: Let the guard own the piped output of a process to forward or capture it, and terminate the process when it stays silent for too long.
+//! - **Pseudo-Terminals**: On Unix, spawn a process in a new pseudo-terminal so that Ctrl+C reaches its whole foreground process group, like in a real terminal.
+//! - **Diagnostic Dumps**: On Unix, capture the thread or goroutine dump of a process that ignores graceful shutdown before it is killed.
+//! - **Kill Snapshots**: On Linux, record the state of a process tree from `/proc` right before killing it.
+//! - **Exit Interpretation**: `ExitInfo` classifies exit codes, signals and core dumps, and tells whether a step of the termination plan ended the process.
+//! - **Pause and Resume**: On Unix, pause a guarded process with `SIGSTOP`, or with the cgroup v2 freezer on Linux, and resume it.
+//! - **Output Routing**: Multiplex the output of many guarded processes into one sink, each line prefixed with the name of its process.
+//! - **Procfile Runner**: Start every process of a Procfile under a guard with the variables of a `.env` file, multiplex their output, and shut them all down when one exits or on Ctrl+C.
+//! - **Signal Forwarding**: On Unix, relay the signals received by the current process, such as `SIGTERM` or `SIGWINCH`, to guarded processes, like a transparent wrapper.
+//! - **Init Mode**: On Linux, run as the PID 1 of a container like `tini`, reaping orphans, forwarding signals to the main process and applying its termination plan on `SIGTERM`.
+//! - **Subreaper**: On Linux, become a child subreaper so that the orphans of guarded processes, such as daemons that double-fork, are terminated with the plan of their guard and reaped.
+//! - **Tree Kill**: On Linux, apply the Ctrl+C and kill steps to every descendant found through `/proc`, top-down or bottom-up, even when they left the process group of the guarded process.
+//! - **PID Namespace**: On Linux, spawn the process in new user and PID namespaces under a tiny init, so that killing it kills every process it started.
+//! - **Spawn Limits**: On Unix, apply kernel resource limits such as CPU time, address space or open files before the command runs, the exit info telling which limit a process exceeded.
+//! - **Resource Usage**: On Unix, the termination outcome reports the CPU time, peak memory, page faults and context switches of the process, collected with `wait4`, along with its lifetime.
+//! - **Scheduling**: On Linux, set the niceness, CPU affinity, OOM score adjustment and I/O priority of the process before the command runs, and read them while it runs.
+//! - **Credentials**: On Unix, run the command as another user, group and supplementary groups, with every capability dropped on Linux, while the guard keeps signaling and killing it.
+//! - **Sandboxing**: On Linux, set `PR_SET_NO_NEW_PRIVS`, deny syscalls with seccomp presets or a custom list, and restrict filesystem access with Landlock before the command runs, the exit info telling a seccomp violation.
+//! - **Network Isolation**: On Linux, spawn processes in a network namespace with only loopback up, unprivileged through a user namespace, shared by the processes that must talk to each other.
 //! - **Error Handling**: Comprehensive error handling to manage process termination scenarios gracefully.
 //!
 //! ### Termination Strategies
@@ -20,6 +40,15 @@
 //! - `CtrlCWaitTimeoutKill(Duration)`: Send a Ctrl+C signal, wait for a specified duration, then kill the process if it hasn't exited.
 //! - `Kill`: Kill the process immediately and do not wait.
 //! - `KillWait`: Kill the process immediately and wait indefinitely for the process to exit.
+//! - `CloseStdin`, `CloseStdinWait`, `CloseStdinWaitTimeout(Duration)`, `CloseStdinWaitTimeoutKill(Duration)`: Close the stdin of the process, then behave like the Ctrl+C strategies.
+//! - `WriteStdin(&[u8])`, `WriteStdinWait(&[u8])`, `WriteStdinWaitTimeout(&[u8], Duration)`, `WriteStdinWaitTimeoutKill(&[u8], Duration)`: Write a command such as `b"quit\n"` to the stdin of the process, then behave like the Ctrl+C strategies.
+//!
+//! ### Termination Plans
+//!
+//! A `TerminationPlan` chains termination steps freely, and mixes the built-in steps with user-defined ones implementing
+//! the `TerminationStep` trait, such as calling an admin endpoint before sending a Ctrl+C signal.
+//! Every `ProcessTermination` converts into the equivalent plan.
+//! On Unix, a plan can ask a hung process for a thread dump with `SIGQUIT` before killing it, the dump is attached to the termination outcome.
 //!
 //! ## Examples
 //!
@@ -38,10 +67,88 @@
 //! let guard = ProcGuard::new(child, ProcessTermination::Wait);
 //! ```
 //!
+#[cfg(unix)]
+mod credentials;
+#[cfg(unix)]
+mod dump;
 mod error;
+mod exit;
+#[cfg(unix)]
+mod forward;
 mod guard;
+#[cfg(target_os = "linux")]
+mod init;
+#[cfg(target_os = "linux")]
+mod monitor;
+#[cfg(target_os = "linux")]
+mod namespace;
+#[cfg(target_os = "linux")]
+mod network;
+mod outcome;
+mod output;
+#[cfg(unix)]
+mod pause;
+mod plan;
 mod platform;
+mod procfile;
+#[cfg(target_os = "linux")]
+mod procfs;
+#[cfg(unix)]
+mod pty;
+#[cfg(unix)]
+mod rlimit;
+mod router;
+#[cfg(target_os = "linux")]
+mod sandbox;
+#[cfg(target_os = "linux")]
+mod scheduling;
+#[cfg(unix)]
+mod shutdown;
+#[cfg(target_os = "linux")]
+mod snapshot;
+mod spawn;
+#[cfg(target_os = "linux")]
+mod subreaper;
+#[cfg(target_os = "linux")]
+mod tree;
+#[cfg(unix)]
+mod usage;
 
+#[cfg(unix)]
+pub use credentials::*;
+#[cfg(unix)]
+pub use dump::*;
 pub use error::*;
+pub use exit::*;
+#[cfg(unix)]
+pub use forward::*;
 pub use guard::*;
+#[cfg(target_os = "linux")]
+pub use init::*;
+#[cfg(target_os = "linux")]
+pub use monitor::*;
+#[cfg(target_os = "linux")]
+pub use network::*;
+pub use outcome::*;
+pub use output::*;
+#[cfg(unix)]
+pub use pause::*;
+pub use plan::*;
 pub use platform::*;
+pub use procfile::*;
+#[cfg(unix)]
+pub use rlimit::*;
+pub use router::*;
+#[cfg(target_os = "linux")]
+pub use sandbox::*;
+#[cfg(target_os = "linux")]
+pub use scheduling::*;
+#[cfg(target_os = "linux")]
+pub use snapshot::*;
+pub use spawn::*;
+#[cfg(target_os = "linux")]
+pub use subreaper::*;
+#[cfg(target_os = "linux")]
+pub use tree::*;
+#[cfg(unix)]
+pub use usage::*;
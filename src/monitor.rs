@@ -0,0 +1,234 @@
+use std::{fmt, fs, io, time::Duration};
+
+/// Default interval between two samples of a monitored process.
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Limits on the resources a guarded process may use before its termination plan is applied.
+///
+/// Limits are checked by sampling `/proc/<pid>` every `interval`, see `ProcGuard::with_resource_limits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    max_rss: Option<u64>,
+    max_cpu_time: Option<Duration>,
+    max_fds: Option<usize>,
+    max_threads: Option<usize>,
+    interval: Duration,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceLimits {
+    /// Creates a set of limits where nothing is limited, sampled every 100 milliseconds.
+    pub fn new() -> Self {
+        ResourceLimits {
+            max_rss: None,
+            max_cpu_time: None,
+            max_fds: None,
+            max_threads: None,
+            interval: DEFAULT_INTERVAL,
+        }
+    }
+
+    /// Limits the resident memory of the process, in bytes.
+    pub fn max_rss(mut self, bytes: u64) -> Self {
+        self.max_rss = Some(bytes);
+        self
+    }
+
+    /// Limits the CPU time (user + system) consumed by the process.
+    pub fn max_cpu_time(mut self, cpu_time: Duration) -> Self {
+        self.max_cpu_time = Some(cpu_time);
+        self
+    }
+
+    /// Limits the number of file descriptors opened by the process.
+    pub fn max_fds(mut self, fds: usize) -> Self {
+        self.max_fds = Some(fds);
+        self
+    }
+
+    /// Limits the number of threads of the process.
+    pub fn max_threads(mut self, threads: usize) -> Self {
+        self.max_threads = Some(threads);
+        self
+    }
+
+    /// Sets the interval between two samples.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the interval between two samples.
+    pub fn sampling_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Checks a sample against the limits.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(LimitExceeded)` - The first limit exceeded by the sample, in the order RSS, CPU time, fds, threads.
+    /// * `None` - If the sample is within every limit.
+    pub fn check(&self, sample: &ResourceSample) -> Option<LimitExceeded> {
+        let exceeded = [
+            self.max_rss
+                .filter(|&max| sample.rss > max)
+                .map(ResourceLimit::Rss),
+            self.max_cpu_time
+                .filter(|&max| sample.cpu_time > max)
+                .map(ResourceLimit::CpuTime),
+            self.max_fds
+                .filter(|&max| sample.fds > max)
+                .map(ResourceLimit::Fds),
+            self.max_threads
+                .filter(|&max| sample.threads > max)
+                .map(ResourceLimit::Threads),
+        ];
+
+        exceeded
+            .into_iter()
+            .flatten()
+            .next()
+            .map(|limit| LimitExceeded {
+                limit,
+                sample: *sample,
+            })
+    }
+}
+
+/// A resource limit along with its configured maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    /// Resident memory, in bytes.
+    Rss(u64),
+    /// CPU time (user + system).
+    CpuTime(Duration),
+    /// Number of open file descriptors.
+    Fds(usize),
+    /// Number of threads.
+    Threads(usize),
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceLimit::Rss(max) => write!(f, "resident memory limit of {} bytes", max),
+            ResourceLimit::CpuTime(max) => write!(f, "CPU time limit of {:?}", max),
+            ResourceLimit::Fds(max) => write!(f, "open file descriptor limit of {}", max),
+            ResourceLimit::Threads(max) => write!(f, "thread limit of {}", max),
+        }
+    }
+}
+
+/// Resources used by a process at the time it was sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceSample {
+    /// Resident memory, in bytes.
+    pub rss: u64,
+    /// CPU time (user + system).
+    pub cpu_time: Duration,
+    /// Number of open file descriptors.
+    pub fds: usize,
+    /// Number of threads.
+    pub threads: usize,
+}
+
+impl fmt::Display for ResourceSample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rss: {} bytes, cpu time: {:?}, fds: {}, threads: {}",
+            self.rss, self.cpu_time, self.fds, self.threads
+        )
+    }
+}
+
+/// Describes which limit was exceeded and the sample that exceeded it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    /// The limit that was exceeded.
+    pub limit: ResourceLimit,
+    /// The sample that exceeded the limit.
+    pub sample: ResourceSample,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exceeded {} ({})", self.limit, self.sample)
+    }
+}
+
+/// Samples the resources used by a process from `/proc/<pid>/status`, `/proc/<pid>/stat` and `/proc/<pid>/fd`.
+///
+/// # Errors
+///
+/// This function returns an I/O error if one of the files cannot be read or parsed, for instance because the process does not exist.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let sample = proc_guard::sample_resources(std::process::id())?;
+/// assert!(sample.threads >= 1);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub fn sample_resources(pid: u32) -> io::Result<ResourceSample> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid))?;
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid))?;
+    let fds = fs::read_dir(format!("/proc/{}/fd", pid))?.count();
+
+    Ok(ResourceSample {
+        // Kernel threads and zombies have no VmRSS line
+        rss: status_field(&status, "VmRSS").unwrap_or(0) * 1024,
+        cpu_time: stat_cpu_time(&stat)?,
+        fds,
+        threads: status_field(&status, "Threads").unwrap_or(0) as usize,
+    })
+}
+
+/// Parses the numeric value of a `Key:   value [kB]` line of `/proc/<pid>/status`.
+fn status_field(status: &str, key: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Parses `utime + stime` of `/proc/<pid>/stat`.
+fn stat_cpu_time(stat: &str) -> io::Result<Duration> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed /proc/<pid>/stat");
+
+    // The command name may contain spaces and parentheses, the fields start after the last ')'
+    let fields: Vec<&str> = stat
+        .rsplit_once(')')
+        .ok_or_else(invalid)?
+        .1
+        .split_whitespace()
+        .collect();
+
+    // utime and stime are the 14th and 15th fields, the first two being the pid and the command name
+    let ticks = |index: usize| -> io::Result<u64> {
+        fields
+            .get(index - 3)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(invalid)
+    };
+    let ticks = ticks(14)? + ticks(15)?;
+
+    let ticks_per_second = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        value if value > 0 => value as u64,
+        _ => 100,
+    };
+
+    Ok(Duration::from_millis(ticks * 1000 / ticks_per_second))
+}
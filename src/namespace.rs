@@ -0,0 +1,169 @@
+use std::{io, mem, os::unix::process::CommandExt, process::Command, ptr};
+
+use libc::{c_int, pid_t, sigset_t};
+
+use crate::platform::sys::{cvt, write_file};
+
+/// Makes `command` run in new user and PID namespaces, under a tiny init.
+///
+/// The spawned process is a relay outside the namespaces whose child is the init, PID 1 of the new PID namespace,
+/// whose child is the workload running `command`. The relay and the init forward every signal they receive to their
+/// child and exit like it, the init reaping the orphans of the namespace meanwhile. Killing the relay kills the init
+/// through its parent death signal, and the kernel kills every process of a PID namespace whose init exits.
+///
+/// The user namespace maps the current user and group to themselves, it only lets an unprivileged process create the
+/// PID namespace.
+///
+/// The closure runs before any other closure of the command but the one joining a network namespace, the following
+/// ones only run in the workload.
+pub(crate) fn configure(command: &mut Command) {
+    // Nothing can be allocated between the fork and the exec
+    let uid_map = format!("{0} {0} 1", unsafe { libc::geteuid() }).into_bytes();
+    let gid_map = format!("{0} {0} 1", unsafe { libc::getegid() }).into_bytes();
+
+    unsafe {
+        command.pre_exec(move || enter(&uid_map, &gid_map));
+    }
+}
+
+/// Creates the namespaces and the init from the spawned process, returns in the workload only.
+fn enter(uid_map: &[u8], gid_map: &[u8]) -> io::Result<()> {
+    cvt(unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWPID) })?;
+    write_file(b"/proc/self/setgroups\0", b"deny")?;
+    write_file(b"/proc/self/uid_map\0", uid_map)?;
+    write_file(b"/proc/self/gid_map\0", gid_map)?;
+
+    // The signals are received with `sigwaitinfo`, they must be blocked before a child can send them
+    let mut all: sigset_t = unsafe { mem::zeroed() };
+    let mut previous: sigset_t = unsafe { mem::zeroed() };
+    unsafe {
+        libc::sigfillset(&mut all);
+        libc::sigprocmask(libc::SIG_SETMASK, &all, &mut previous);
+    }
+
+    // The write end is held by the relay only, it is closed once the relay exited
+    let mut relay_alive = [0 as c_int; 2];
+    cvt(unsafe { libc::pipe2(relay_alive.as_mut_ptr(), libc::O_CLOEXEC) })?;
+    match cvt(unsafe { libc::fork() })? {
+        0 => {}
+        init => relay(init, relay_alive[1], &all),
+    }
+
+    // The init, PID 1 of the new namespace
+    unsafe {
+        libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+        libc::close(relay_alive[1]);
+    }
+    // The relay may have been killed before the parent death signal was set
+    let mut poll = libc::pollfd {
+        fd: relay_alive[0],
+        events: 0,
+        revents: 0,
+    };
+    if unsafe { libc::poll(&mut poll, 1, 0) } != 0 {
+        unsafe { libc::_exit(1) };
+    }
+
+    match unsafe { libc::fork() } {
+        0 => {
+            // The workload, which runs the command as PID 2
+            unsafe {
+                libc::close(relay_alive[0]);
+                libc::sigprocmask(libc::SIG_SETMASK, &previous, ptr::null_mut());
+            }
+            Ok(())
+        }
+        // The command cannot run, the error cannot be reported anymore
+        -1 => unsafe { libc::_exit(127) },
+        workload => init(workload, &all),
+    }
+}
+
+/// Runs the relay, forwarding the signals to the init and exiting like it.
+fn relay(init: pid_t, relay_alive: c_int, signals: &sigset_t) -> ! {
+    // The pipe reporting a failed exec to the parent must only be held by the workload
+    close_fds(relay_alive);
+
+    loop {
+        match wait_signal(signals) {
+            libc::SIGCHLD => {
+                let mut status = 0;
+                if unsafe { libc::waitpid(init, &mut status, libc::WNOHANG) } == init {
+                    exit_like(status);
+                }
+            }
+            signal => unsafe {
+                libc::kill(init, signal);
+            },
+        }
+    }
+}
+
+/// Runs the init, forwarding the signals to the workload and reaping every orphan until the workload exited.
+fn init(workload: pid_t, signals: &sigset_t) -> ! {
+    close_fds(-1);
+
+    loop {
+        match wait_signal(signals) {
+            libc::SIGCHLD => loop {
+                let mut status = 0;
+                match unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) } {
+                    // Exiting kills the remaining processes of the namespace
+                    pid if pid == workload => exit_like(status),
+                    pid if pid > 0 => {}
+                    _ => break,
+                }
+            },
+            signal => unsafe {
+                libc::kill(workload, signal);
+            },
+        }
+    }
+}
+
+/// Waits for one of the blocked `signals`.
+fn wait_signal(signals: &sigset_t) -> c_int {
+    loop {
+        let signal = unsafe { libc::sigwaitinfo(signals, ptr::null_mut()) };
+        if signal > 0 {
+            return signal;
+        }
+    }
+}
+
+/// Exits with the wait `status` of a child, a child killed by a signal making the code 128 plus the signal as in a shell.
+///
+/// The init cannot be killed by a signal it sends to itself.
+fn exit_like(status: c_int) -> ! {
+    let code = if libc::WIFSIGNALED(status) {
+        128 + libc::WTERMSIG(status)
+    } else {
+        libc::WEXITSTATUS(status)
+    };
+    unsafe { libc::_exit(code) }
+}
+
+/// Closes every file descriptor but `keep`.
+fn close_fds(keep: c_int) {
+    // `close_range` exists since Linux 5.9
+    let ranges = [(0, keep - 1), (keep + 1, c_int::MAX)];
+    let closed = ranges
+        .iter()
+        .filter(|(first, last)| first <= last)
+        .all(|&(first, last)| unsafe {
+            libc::syscall(libc::SYS_close_range, first as u32, last as u32, 0) == 0
+        });
+    if closed {
+        return;
+    }
+
+    let max = unsafe { libc::sysconf(libc::_SC_OPEN_MAX) };
+    let max = if max < 0 {
+        1024
+    } else {
+        max.min(65536) as c_int
+    };
+    for fd in (0..max).filter(|&fd| fd != keep) {
+        unsafe { libc::close(fd) };
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    fs::File,
+    io, mem,
+    os::{fd::AsRawFd, unix::process::CommandExt},
+    process::Command,
+    sync::Arc,
+};
+
+use libc::{c_int, pid_t};
+
+use crate::platform::sys::{cvt, write_file};
+
+/// A network namespace with only the loopback interface, up, for guarded processes that must not reach the network
+/// nor clash on ports with the host, see `SpawnOptions::network_namespace`.
+///
+/// The namespace is owned by a new user namespace mapping the current user and group to themselves, so that no
+/// privilege is needed. Every process spawned with the same namespace shares its loopback interface, so that a test
+/// server and its clients can talk to each other, and the namespace lives until the last of them and every clone of
+/// this value are gone.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use proc_guard::{NetworkNamespace, ProcGuard, ProcessTermination, SpawnOptions};
+///
+/// let namespace = NetworkNamespace::new()?;
+/// let server = ProcGuard::spawn_with(
+///     Command::new("my-server").args(["--listen", "127.0.0.1:8080"]),
+///     ProcessTermination::CtrlCWait,
+///     SpawnOptions::new().network_namespace(&namespace),
+/// )?;
+/// // Reaches the server, but neither the host nor the network
+/// let client = ProcGuard::spawn_with(
+///     Command::new("curl").arg("http://127.0.0.1:8080/"),
+///     ProcessTermination::Wait,
+///     SpawnOptions::new().network_namespace(&namespace),
+/// )?;
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NetworkNamespace(Arc<Namespaces>);
+
+/// The user and network namespaces, kept alive by their open files.
+#[derive(Debug)]
+struct Namespaces {
+    user: File,
+    net: File,
+}
+
+impl NetworkNamespace {
+    /// Creates a network namespace whose loopback interface is up.
+    ///
+    /// # Errors
+    ///
+    /// * `io::Error` - If the system does not allow unprivileged user namespaces, or if setting up the namespace failed.
+    pub fn new() -> io::Result<Self> {
+        // Nothing can be allocated in the forked process
+        let uid_map = format!("{0} {0} 1", unsafe { libc::geteuid() }).into_bytes();
+        let gid_map = format!("{0} {0} 1", unsafe { libc::getegid() }).into_bytes();
+
+        // The holder creates the namespaces and reports 0, or the errno of the step that failed
+        let mut ready = [0 as c_int; 2];
+        cvt(unsafe { libc::pipe2(ready.as_mut_ptr(), libc::O_CLOEXEC) })?;
+        let holder = match unsafe { libc::fork() } {
+            -1 => {
+                let error = io::Error::last_os_error();
+                unsafe {
+                    libc::close(ready[0]);
+                    libc::close(ready[1]);
+                }
+                return Err(error);
+            }
+            0 => hold(ready[1], &uid_map, &gid_map),
+            holder => holder,
+        };
+        unsafe { libc::close(ready[1]) };
+
+        let mut errno: c_int = 0;
+        let read = unsafe {
+            libc::read(
+                ready[0],
+                (&mut errno as *mut c_int).cast(),
+                mem::size_of::<c_int>(),
+            )
+        };
+        unsafe { libc::close(ready[0]) };
+        let result = match read {
+            -1 => Err(io::Error::last_os_error()),
+            read if read as usize != mem::size_of::<c_int>() => {
+                Err(io::ErrorKind::UnexpectedEof.into())
+            }
+            _ if errno != 0 => Err(io::Error::from_raw_os_error(errno)),
+            // The open files keep the namespaces alive once the holder is gone
+            _ => open_namespaces(holder),
+        };
+
+        unsafe {
+            libc::kill(holder, libc::SIGKILL);
+            libc::waitpid(holder, std::ptr::null_mut(), 0);
+        }
+        Ok(NetworkNamespace(Arc::new(result?)))
+    }
+
+    /// Makes the process spawned by `command` join the namespace before it runs the command.
+    ///
+    /// The closure must run before the one creating a PID namespace, whose user namespace is then nested in the one of
+    /// the network namespace.
+    pub(crate) fn configure(&self, command: &mut Command) {
+        let namespaces = self.0.clone();
+
+        unsafe {
+            command.pre_exec(move || {
+                // The user namespace grants the capabilities needed to join the network namespace
+                cvt(libc::setns(
+                    namespaces.user.as_raw_fd(),
+                    libc::CLONE_NEWUSER,
+                ))?;
+                cvt(libc::setns(namespaces.net.as_raw_fd(), libc::CLONE_NEWNET))?;
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Opens the namespaces of the holder.
+fn open_namespaces(holder: pid_t) -> io::Result<Namespaces> {
+    Ok(Namespaces {
+        user: File::open(format!("/proc/{}/ns/user", holder))?,
+        net: File::open(format!("/proc/{}/ns/net", holder))?,
+    })
+}
+
+/// Runs the holder, which creates the namespaces, reports to `ready` and waits to be killed.
+fn hold(ready: c_int, uid_map: &[u8], gid_map: &[u8]) -> ! {
+    unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) };
+
+    let errno = match enter(uid_map, gid_map) {
+        Ok(()) => 0,
+        Err(error) => error.raw_os_error().unwrap_or(libc::EIO),
+    };
+    unsafe {
+        libc::write(
+            ready,
+            (&errno as *const c_int).cast(),
+            mem::size_of::<c_int>(),
+        )
+    };
+    if errno != 0 {
+        unsafe { libc::_exit(1) };
+    }
+    loop {
+        unsafe { libc::pause() };
+    }
+}
+
+/// Creates the namespaces from the holder and brings the loopback interface up.
+fn enter(uid_map: &[u8], gid_map: &[u8]) -> io::Result<()> {
+    cvt(unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) })?;
+    write_file(b"/proc/self/setgroups\0", b"deny")?;
+    write_file(b"/proc/self/uid_map\0", uid_map)?;
+    write_file(b"/proc/self/gid_map\0", gid_map)?;
+
+    let socket = cvt(unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) })?;
+    let mut request: libc::ifreq = unsafe { mem::zeroed() };
+    for (name, &byte) in request.ifr_name.iter_mut().zip(b"lo") {
+        *name = byte as libc::c_char;
+    }
+    cvt(unsafe { libc::ioctl(socket, libc::SIOCGIFFLAGS, &mut request) })?;
+    unsafe { request.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short };
+    cvt(unsafe { libc::ioctl(socket, libc::SIOCSIFFLAGS, &request) })?;
+    unsafe { libc::close(socket) };
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::{process::ExitStatus, time::Duration};
+
+#[cfg(unix)]
+use crate::dump::Diagnostics;
+#[cfg(target_os = "linux")]
+use crate::monitor::LimitExceeded;
+#[cfg(target_os = "linux")]
+use crate::snapshot::ProcSnapshot;
+#[cfg(target_os = "linux")]
+use crate::subreaper::ReapedOrphan;
+#[cfg(unix)]
+use crate::usage::ResourceUsage;
+use crate::{
+    exit::ExitInfo,
+    output::{CapturedOutput, RecentOutput},
+};
+
+/// Describes how a supervised process ended.
+#[derive(Debug)]
+pub struct TerminationOutcome {
+    /// The exit status of the process.
+    /// `None` if the termination plan did not wait for the process after terminating it.
+    pub status: Option<ExitStatus>,
+    /// The interpretation of `status`, telling whether a step of the termination plan ended the process.
+    pub exit: Option<ExitInfo>,
+    /// The reason the process ended.
+    pub reason: TerminationReason,
+    /// The output of the process, if the guard captured it with `OutputMode::Capture`.
+    pub output: Option<CapturedOutput>,
+    /// The most recent output of the process, if the guard kept it with `ProcGuard::with_recent_output`.
+    pub recent_output: Option<RecentOutput>,
+    /// The diagnostics recorded by the dump steps of the termination plan, in the order they ran.
+    #[cfg(unix)]
+    pub diagnostics: Vec<Diagnostics>,
+    /// The snapshots taken before the kill steps of the termination plan, if requested with `ProcGuard::with_kill_snapshot`.
+    #[cfg(target_os = "linux")]
+    pub snapshots: Vec<ProcSnapshot>,
+    /// The orphaned descendants of the process reaped by the current process, if linked with `ProcGuard::with_subreaper`.
+    #[cfg(target_os = "linux")]
+    pub orphans: Vec<ReapedOrphan>,
+    /// The wall-clock time from the spawn of the process, or the creation of its guard, until it was reaped.
+    /// If the termination plan did not wait for the process, until the termination ended.
+    pub lifetime: Duration,
+    /// The resources used by the process and its reaped descendants, `None` if the process was not reaped by its
+    /// guard, such as when the termination plan did not wait for it or when it was waited for through `mut_child`.
+    #[cfg(unix)]
+    pub usage: Option<ResourceUsage>,
+}
+
+/// Enum representing the reasons for which a supervised process ended.
+#[derive(Debug, Clone)]
+pub enum TerminationReason {
+    /// The process exited on its own.
+    Exited,
+    /// The termination strategy was applied on request, see `ProcGuard::terminate_with_output`.
+    Requested,
+    /// The process did not write anything on its piped output during the configured timeout, and the termination strategy was applied.
+    IdleTimeout(Duration),
+    /// A resource limit was exceeded and the termination strategy was applied.
+    #[cfg(target_os = "linux")]
+    LimitExceeded(LimitExceeded),
+}
@@ -0,0 +1,645 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Read, Write},
+    process::Child,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::router::Route;
+
+/// Size of the buffer used to read the output of a process.
+const CHUNK_SIZE: usize = 8192;
+
+/// Maximum length kept of a line that is not terminated yet.
+const MAX_PARTIAL_LINE: usize = 64 * 1024;
+
+/// Maximum number of lines kept for `wait_for_line` until they are consumed, the oldest ones are dropped first.
+const MAX_PENDING_LINES: usize = 10_000;
+
+/// Maximum number of bytes of the lines kept for `wait_for_line` until they are consumed.
+const MAX_PENDING_BYTES: usize = 1024 * 1024;
+
+/// Enum representing what a guard does with the piped output of its process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Forward the output to the stdout and stderr of the current process.
+    Forward,
+    /// Capture the output in memory, it is returned in the termination outcome.
+    Capture,
+    /// Read the output and discard it, so that the process never blocks on a full pipe.
+    Discard,
+}
+
+/// The output captured from a guarded process.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapturedOutput {
+    /// The bytes written by the process to its stdout.
+    pub stdout: Vec<u8>,
+    /// The bytes written by the process to its stderr.
+    pub stderr: Vec<u8>,
+}
+
+/// Enum representing the output streams of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    /// The standard output.
+    Stdout,
+    /// The standard error.
+    Stderr,
+}
+
+impl fmt::Display for OutputStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputStream::Stdout => write!(f, "stdout"),
+            OutputStream::Stderr => write!(f, "stderr"),
+        }
+    }
+}
+
+/// Enum representing the bound of the ring buffer keeping the most recent output of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecentOutputLimit {
+    /// Keep the last N lines.
+    Lines(usize),
+    /// Keep the last lines whose total length does not exceed N bytes.
+    Bytes(usize),
+}
+
+/// A line written by a process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLine {
+    /// The stream the line was written to.
+    pub stream: OutputStream,
+    /// The line, without its line terminator. Invalid UTF-8 is replaced by `U+FFFD`.
+    pub text: String,
+}
+
+/// The most recent lines written by a process on its stdout and stderr, in the order they were read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecentOutput {
+    /// The lines, from the oldest to the most recent.
+    pub lines: Vec<OutputLine>,
+}
+
+impl fmt::Display for RecentOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "[{}] {}", line.stream, line.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Trait for the matchers used by `ProcGuard::wait_for_line`.
+///
+/// It is implemented for `&str` and `String`, which match the lines containing them, and for closures taking a line.
+pub trait LineMatcher {
+    /// Returns whether the line, without its line terminator, matches.
+    fn matches(&mut self, line: &str) -> bool;
+}
+
+impl LineMatcher for &str {
+    fn matches(&mut self, line: &str) -> bool {
+        line.contains(*self)
+    }
+}
+
+impl LineMatcher for String {
+    fn matches(&mut self, line: &str) -> bool {
+        line.contains(self.as_str())
+    }
+}
+
+impl<F: FnMut(&str) -> bool> LineMatcher for F {
+    fn matches(&mut self, line: &str) -> bool {
+        self(line)
+    }
+}
+
+/// Splits chunks of output into lines, keeping the last line of each stream until it is terminated.
+#[derive(Debug)]
+pub(crate) struct LineSplitter {
+    max_partial: usize,
+    partial_stdout: Vec<u8>,
+    partial_stderr: Vec<u8>,
+}
+
+impl LineSplitter {
+    /// Creates a splitter keeping at most `max_partial` bytes of a line that is not terminated yet.
+    pub(crate) fn new(max_partial: usize) -> Self {
+        LineSplitter {
+            max_partial: max_partial.min(MAX_PARTIAL_LINE),
+            partial_stdout: Vec::new(),
+            partial_stderr: Vec::new(),
+        }
+    }
+
+    /// Splits a chunk of output and calls `on_line` with every line it terminates.
+    pub(crate) fn push(
+        &mut self,
+        stream: OutputStream,
+        chunk: &[u8],
+        mut on_line: impl FnMut(OutputLine),
+    ) {
+        let max_partial = self.max_partial;
+        let partial = self.partial(stream);
+
+        let mut segments = chunk.split(|&byte| byte == b'\n');
+        let last = segments.next_back().unwrap_or_default();
+        for segment in segments {
+            partial.extend_from_slice(segment);
+            on_line(to_line(stream, &std::mem::take(partial)));
+        }
+
+        partial.extend_from_slice(last);
+        if partial.len() > max_partial {
+            partial.drain(..partial.len() - max_partial);
+        }
+    }
+
+    /// Calls `on_line` with the line of `stream` that is not terminated, if any, as the stream ended.
+    pub(crate) fn flush(&mut self, stream: OutputStream, mut on_line: impl FnMut(OutputLine)) {
+        let partial = self.partial(stream);
+        if !partial.is_empty() {
+            on_line(to_line(stream, &std::mem::take(partial)));
+        }
+    }
+
+    /// Returns the lines that are not terminated yet.
+    fn partial_lines(&self) -> impl Iterator<Item = OutputLine> + '_ {
+        [
+            (OutputStream::Stdout, &self.partial_stdout),
+            (OutputStream::Stderr, &self.partial_stderr),
+        ]
+        .into_iter()
+        .filter(|(_, partial)| !partial.is_empty())
+        .map(|(stream, partial)| to_line(stream, partial))
+    }
+
+    fn partial(&mut self, stream: OutputStream) -> &mut Vec<u8> {
+        match stream {
+            OutputStream::Stdout => &mut self.partial_stdout,
+            OutputStream::Stderr => &mut self.partial_stderr,
+        }
+    }
+}
+
+/// Converts a line without its `\n` terminator.
+fn to_line(stream: OutputStream, line: &[u8]) -> OutputLine {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    OutputLine {
+        stream,
+        text: String::from_utf8_lossy(line).into_owned(),
+    }
+}
+
+/// Ring buffer of the most recent lines written by a process.
+#[derive(Debug)]
+struct RecentBuffer {
+    limit: RecentOutputLimit,
+    lines: VecDeque<OutputLine>,
+    bytes: usize,
+    splitter: LineSplitter,
+}
+
+impl RecentBuffer {
+    fn new(limit: RecentOutputLimit) -> Self {
+        let max_partial = match limit {
+            RecentOutputLimit::Bytes(max) => max,
+            RecentOutputLimit::Lines(_) => MAX_PARTIAL_LINE,
+        };
+
+        RecentBuffer {
+            limit,
+            lines: VecDeque::new(),
+            bytes: 0,
+            splitter: LineSplitter::new(max_partial),
+        }
+    }
+
+    fn push(&mut self, stream: OutputStream, chunk: &[u8]) {
+        let RecentBuffer {
+            limit,
+            lines,
+            bytes,
+            splitter,
+        } = self;
+        splitter.push(stream, chunk, |line| {
+            push_bounded(lines, bytes, *limit, line)
+        });
+    }
+
+    fn flush(&mut self, stream: OutputStream) {
+        let RecentBuffer {
+            limit,
+            lines,
+            bytes,
+            splitter,
+        } = self;
+        splitter.flush(stream, |line| push_bounded(lines, bytes, *limit, line));
+    }
+
+    /// Returns the buffered lines, including the lines that are not terminated yet.
+    fn snapshot(&self) -> RecentOutput {
+        let mut lines = self.lines.clone();
+        let mut bytes = self.bytes;
+        for line in self.splitter.partial_lines() {
+            push_bounded(&mut lines, &mut bytes, self.limit, line);
+        }
+
+        RecentOutput {
+            lines: lines.into(),
+        }
+    }
+}
+
+/// Pushes a line in a ring buffer, then drops the oldest lines until the buffer is within `limit`.
+fn push_bounded(
+    lines: &mut VecDeque<OutputLine>,
+    bytes: &mut usize,
+    limit: RecentOutputLimit,
+    line: OutputLine,
+) {
+    *bytes += line.text.len();
+    lines.push_back(line);
+
+    loop {
+        let exceeded = match limit {
+            RecentOutputLimit::Lines(max) => lines.len() > max,
+            RecentOutputLimit::Bytes(max) => *bytes > max,
+        };
+        if !exceeded {
+            break;
+        }
+        match lines.pop_front() {
+            Some(line) => *bytes -= line.text.len(),
+            None => break,
+        }
+    }
+}
+
+/// Lines read from a process that were not consumed yet, the oldest ones being dropped once `MAX_PENDING_LINES` or
+/// `MAX_PENDING_BYTES` are exceeded.
+#[derive(Debug)]
+struct PendingLines {
+    lines: VecDeque<OutputLine>,
+    bytes: usize,
+    splitter: LineSplitter,
+}
+
+impl PendingLines {
+    fn new() -> Self {
+        PendingLines {
+            lines: VecDeque::new(),
+            bytes: 0,
+            splitter: LineSplitter::new(MAX_PARTIAL_LINE),
+        }
+    }
+
+    fn push(&mut self, stream: OutputStream, chunk: &[u8]) {
+        let PendingLines {
+            lines,
+            bytes,
+            splitter,
+        } = self;
+        splitter.push(stream, chunk, |line| push_pending(lines, bytes, line));
+    }
+
+    fn flush(&mut self, stream: OutputStream) {
+        let PendingLines {
+            lines,
+            bytes,
+            splitter,
+        } = self;
+        splitter.flush(stream, |line| push_pending(lines, bytes, line));
+    }
+
+    fn pop(&mut self) -> Option<OutputLine> {
+        let line = self.lines.pop_front()?;
+        self.bytes -= line.text.len();
+        Some(line)
+    }
+
+    fn take(&mut self) -> Vec<OutputLine> {
+        self.bytes = 0;
+        self.lines.drain(..).collect()
+    }
+}
+
+/// Pushes a line that was not consumed yet, then drops the oldest lines until at most `MAX_PENDING_LINES` and
+/// `MAX_PENDING_BYTES` are kept.
+fn push_pending(lines: &mut VecDeque<OutputLine>, bytes: &mut usize, line: OutputLine) {
+    *bytes += line.text.len();
+    lines.push_back(line);
+
+    while lines.len() > MAX_PENDING_LINES || *bytes > MAX_PENDING_BYTES {
+        match lines.pop_front() {
+            Some(line) => *bytes -= line.text.len(),
+            None => break,
+        }
+    }
+}
+
+/// Reads the piped output of a process from background threads so that the process never blocks on a full pipe.
+#[derive(Debug)]
+pub(crate) struct OutputPump {
+    mode: OutputMode,
+    /// The number of piped streams read, known once started.
+    streams: usize,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified when a line becomes pending or a stream ends.
+    changed: Condvar,
+}
+
+#[derive(Debug)]
+struct State {
+    route: Option<Route>,
+    last_activity: Instant,
+    open_streams: usize,
+    captured: CapturedOutput,
+    recent: Option<RecentBuffer>,
+    pending: Option<PendingLines>,
+    recording: Option<(OutputStream, Vec<u8>)>,
+}
+
+impl OutputPump {
+    /// Creates a pump that does not read anything until `start` is called.
+    pub(crate) fn new(mode: OutputMode) -> Self {
+        OutputPump {
+            mode,
+            streams: 0,
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    route: None,
+                    last_activity: Instant::now(),
+                    open_streams: 0,
+                    captured: CapturedOutput::default(),
+                    recent: None,
+                    pending: None,
+                    recording: None,
+                }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Sends every chunk read from now on to an output router as well, replacing the previous route if any.
+    ///
+    /// The output of the previous route ends, its last lines being written even if they are not terminated.
+    pub(crate) fn route(&self, route: Route) {
+        let previous = self.shared.lock().route.replace(route);
+        if let Some(previous) = previous {
+            previous.end(OutputStream::Stdout);
+            previous.end(OutputStream::Stderr);
+        }
+    }
+
+    /// Takes the piped stdout and stderr of the child and starts reading them.
+    /// Streams that are not piped are ignored.
+    pub(crate) fn start(mut self, child: &mut Child) -> Self {
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        self.streams = stdout.is_some() as usize + stderr.is_some() as usize;
+
+        {
+            let mut state = self.shared.lock();
+            state.last_activity = Instant::now();
+            state.open_streams = self.streams;
+        }
+
+        if let Some(stdout) = stdout {
+            spawn_reader(stdout, OutputStream::Stdout, &self);
+        }
+        if let Some(stderr) = stderr {
+            spawn_reader(stderr, OutputStream::Stderr, &self);
+        }
+
+        self
+    }
+
+    /// Starts keeping the most recent output of the process in a ring buffer, replacing the previous one if any.
+    pub(crate) fn keep_recent(&self, limit: RecentOutputLimit) {
+        self.shared.lock().recent = Some(RecentBuffer::new(limit));
+    }
+
+    /// Starts keeping the lines read until they are consumed by `wait_for_line` or `take_pending_lines`, if they are
+    /// not kept yet.
+    pub(crate) fn keep_pending(&self) {
+        self.shared
+            .lock()
+            .pending
+            .get_or_insert_with(PendingLines::new);
+    }
+
+    /// Returns the most recent output of the process, if a ring buffer is kept.
+    pub(crate) fn recent(&self) -> Option<RecentOutput> {
+        self.shared
+            .lock()
+            .recent
+            .as_ref()
+            .map(RecentBuffer::snapshot)
+    }
+
+    /// Consumes the lines kept since `keep_pending` was called, or since the last line consumed, until one matches,
+    /// waiting at most `timeout` for new lines.
+    ///
+    /// Lines are kept from now on if they were not kept yet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(OutputLine)` - The first line that matches.
+    /// * `Err(Vec<OutputLine>)` - The lines consumed, if the timeout elapsed or every stream ended before a line matched.
+    pub(crate) fn wait_for_line(
+        &self,
+        matcher: &mut dyn LineMatcher,
+        timeout: Duration,
+    ) -> Result<OutputLine, Vec<OutputLine>> {
+        let deadline = Instant::now() + timeout;
+        let mut seen = Vec::new();
+        let mut state = self.shared.lock();
+        state.pending.get_or_insert_with(PendingLines::new);
+
+        loop {
+            while let Some(line) = state.pending.as_mut().and_then(PendingLines::pop) {
+                if matcher.matches(&line.text) {
+                    return Ok(line);
+                }
+                seen.push(line);
+            }
+
+            let now = Instant::now();
+            if state.open_streams == 0 || now >= deadline {
+                return Err(seen);
+            }
+
+            state = self
+                .shared
+                .changed
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Consumes the pending lines, keeping the lines read from now on if they were not kept yet.
+    pub(crate) fn take_pending_lines(&self) -> Vec<OutputLine> {
+        self.shared
+            .lock()
+            .pending
+            .get_or_insert_with(PendingLines::new)
+            .take()
+    }
+
+    /// Starts recording the raw bytes read from `stream`, replacing the previous recording if any.
+    #[cfg(unix)]
+    pub(crate) fn record(&self, stream: OutputStream) {
+        self.shared.lock().recording = Some((stream, Vec::new()));
+    }
+
+    /// Stops recording and returns the bytes recorded since `record` was called.
+    #[cfg(unix)]
+    pub(crate) fn take_recorded(&self) -> Vec<u8> {
+        self.shared
+            .lock()
+            .recording
+            .take()
+            .map(|(_, recorded)| recorded)
+            .unwrap_or_default()
+    }
+
+    /// Returns whether the pump reads at least one piped stream.
+    pub(crate) fn reads_output(&self) -> bool {
+        self.streams > 0
+    }
+
+    /// Returns how long the process has been silent on every stream.
+    pub(crate) fn idle_for(&self) -> Duration {
+        self.shared.lock().last_activity.elapsed()
+    }
+
+    /// Waits for every stream to reach its end, at most `grace`, and returns the captured output.
+    ///
+    /// The grace period avoids blocking forever when a descendant of the process still holds the pipes open.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(CapturedOutput)` - The output captured so far, if the mode is `OutputMode::Capture`.
+    /// * `None` - If the output is not captured.
+    pub(crate) fn finish(&self, grace: Duration) -> Option<CapturedOutput> {
+        let mut state = self.wait_closed(grace);
+        match self.mode {
+            OutputMode::Capture => Some(std::mem::take(&mut state.captured)),
+            OutputMode::Forward | OutputMode::Discard => None,
+        }
+    }
+
+    /// Returns whether the output is sent to an output router.
+    pub(crate) fn is_routed(&self) -> bool {
+        self.shared.lock().route.is_some()
+    }
+
+    /// Waits for every stream to reach its end, at most `timeout`, so that the output written before exiting is read.
+    pub(crate) fn settle(&self, timeout: Duration) {
+        drop(self.wait_closed(timeout));
+    }
+
+    /// Waits for every stream to reach its end, at most `timeout`, and returns the locked state.
+    fn wait_closed(&self, timeout: Duration) -> MutexGuard<'_, State> {
+        let state = self.shared.lock();
+        self.shared
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.open_streams > 0)
+            .unwrap_or_else(PoisonError::into_inner)
+            .0
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Spawns a thread reading `reader` until its end.
+fn spawn_reader<R: Read + Send + 'static>(mut reader: R, stream: OutputStream, pump: &OutputPump) {
+    let mode = pump.mode;
+    let shared = pump.shared.clone();
+
+    thread::spawn(move || {
+        let mut buffer = [0u8; CHUNK_SIZE];
+
+        loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            let chunk = &buffer[..read];
+
+            // A slow router blocks this thread only, the state stays available meanwhile
+            let route = shared.lock().route.clone();
+            if let Some(route) = route {
+                route.chunk(stream, chunk);
+            }
+
+            let mut state = shared.lock();
+            state.last_activity = Instant::now();
+            if let Some(recent) = &mut state.recent {
+                recent.push(stream, chunk);
+            }
+            if let Some(pending) = &mut state.pending {
+                pending.push(stream, chunk);
+                if !pending.lines.is_empty() {
+                    shared.changed.notify_all();
+                }
+            }
+            if let Some((recorded_stream, recorded)) = &mut state.recording {
+                if *recorded_stream == stream {
+                    recorded.extend(chunk);
+                }
+            }
+            match (mode, stream) {
+                (OutputMode::Capture, OutputStream::Stdout) => state.captured.stdout.extend(chunk),
+                (OutputMode::Capture, OutputStream::Stderr) => state.captured.stderr.extend(chunk),
+                // Forwarding errors are ignored, the process must keep being drained
+                (OutputMode::Forward, OutputStream::Stdout) => {
+                    drop(state);
+                    let _ = io::stdout().write_all(chunk);
+                }
+                (OutputMode::Forward, OutputStream::Stderr) => {
+                    drop(state);
+                    let _ = io::stderr().write_all(chunk);
+                }
+                (OutputMode::Discard, _) => {}
+            }
+        }
+
+        let route = shared.lock().route.clone();
+        if let Some(route) = route {
+            route.end(stream);
+        }
+
+        let mut state = shared.lock();
+        if let Some(recent) = &mut state.recent {
+            recent.flush(stream);
+        }
+        if let Some(pending) = &mut state.pending {
+            pending.flush(stream);
+        }
+        state.open_streams -= 1;
+        shared.changed.notify_all();
+    });
+}
@@ -0,0 +1,202 @@
+use std::{io, process::Child};
+
+#[cfg(target_os = "linux")]
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use libc::{kill, pid_t, SIGCONT, SIGSTOP};
+
+#[cfg(target_os = "linux")]
+use crate::procfs;
+
+/// How long pausing or resuming waits for the process or its cgroup to report the new state.
+#[cfg(target_os = "linux")]
+const STATE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Enum representing how a guarded process was paused, see `ProcGuard::pause`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseMethod {
+    /// The process was sent `SIGSTOP` and is resumed with `SIGCONT`.
+    Signal,
+    /// The cgroup v2 of the process was frozen, the path being the cgroup directory.
+    /// The whole cgroup is frozen, which only holds the process and its descendants.
+    #[cfg(target_os = "linux")]
+    CgroupFreeze(PathBuf),
+}
+
+/// Pauses a process, freezing its cgroup if it has its own cgroup v2 and sending `SIGSTOP` otherwise.
+///
+/// A cgroup is the process' own if it holds nothing but the process and its descendants.
+pub(crate) fn pause(child: &Child) -> io::Result<PauseMethod> {
+    #[cfg(target_os = "linux")]
+    if let Some(cgroup) = own_cgroup(child.id()) {
+        freeze(&cgroup, true)?;
+        return Ok(PauseMethod::CgroupFreeze(cgroup));
+    }
+
+    signal(child, SIGSTOP)?;
+    #[cfg(target_os = "linux")]
+    wait_state(child.id(), true)?;
+    Ok(PauseMethod::Signal)
+}
+
+/// Resumes a process paused with `method`, a process that was already reaped being considered resumed.
+pub(crate) fn resume(child: &Child, method: &PauseMethod) -> io::Result<()> {
+    match method {
+        PauseMethod::Signal => match signal(child, SIGCONT) {
+            Err(e) if e.raw_os_error() == Some(libc::ESRCH) => Ok(()),
+            result => {
+                result?;
+                #[cfg(target_os = "linux")]
+                wait_state(child.id(), false)?;
+                Ok(())
+            }
+        },
+        #[cfg(target_os = "linux")]
+        PauseMethod::CgroupFreeze(cgroup) => match freeze(cgroup, false) {
+            // The cgroup may be removed once the process exited
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+    }
+}
+
+/// Resumes a process stopped by someone else, such as a `SIGSTOP` or a Ctrl+Z from its terminal.
+///
+/// Only Linux tells whether a process is stopped, on other platforms this does nothing.
+pub(crate) fn resume_stopped(child: &Child) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    if is_stopped(child.id()) {
+        return signal(child, SIGCONT);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = child;
+
+    Ok(())
+}
+
+fn signal(child: &Child, signal: i32) -> io::Result<()> {
+    if unsafe { kill(child.id() as pid_t, signal) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Returns whether a process is stopped by a signal, from the state of `/proc/<pid>/stat`.
+///
+/// A process stopped by a debugger, in the `t` state, is not stopped by a signal and `SIGCONT` would not resume it.
+#[cfg(target_os = "linux")]
+fn is_stopped(pid: u32) -> bool {
+    procfs::ProcStat::read(pid).is_some_and(|stat| stat.state == 'T')
+}
+
+/// Waits for a process to be stopped or running, signals being delivered asynchronously.
+#[cfg(target_os = "linux")]
+fn wait_state(pid: u32, stopped: bool) -> io::Result<()> {
+    poll(
+        || Ok(is_stopped(pid) == stopped),
+        || {
+            format!(
+                "process {} is not {}",
+                pid,
+                if stopped { "stopped" } else { "resumed" }
+            )
+        },
+    )
+}
+
+/// Returns the cgroup v2 directory of a process, if it holds nothing but the process and its descendants.
+/// Freezing a cgroup shared with other processes, such as a systemd slice or the cgroup of the current process, would
+/// freeze them too.
+#[cfg(target_os = "linux")]
+fn own_cgroup(pid: u32) -> Option<PathBuf> {
+    let cgroup = unified_cgroup(&pid.to_string())?;
+    let directory = cgroup2_mount()?.join(cgroup.trim_start_matches('/'));
+    if !directory.join("cgroup.freeze").exists() {
+        return None;
+    }
+
+    let tree: HashSet<u32> = procfs::descendants(pid)
+        .iter()
+        .map(|process| process.pid)
+        .chain(std::iter::once(pid))
+        .collect();
+    cgroup_processes(&directory)?
+        .iter()
+        .all(|process| tree.contains(process))
+        .then_some(directory)
+}
+
+/// Returns the processes of a cgroup and of the cgroups below it, which are frozen with it.
+#[cfg(target_os = "linux")]
+fn cgroup_processes(cgroup: &Path) -> Option<Vec<u32>> {
+    let mut processes: Vec<u32> = fs::read_to_string(cgroup.join("cgroup.procs"))
+        .ok()?
+        .lines()
+        .filter_map(|line| line.parse().ok())
+        .collect();
+    for entry in fs::read_dir(cgroup).ok()? {
+        let entry = entry.ok()?;
+        if entry.file_type().ok()?.is_dir() {
+            processes.extend(cgroup_processes(&entry.path())?);
+        }
+    }
+    Some(processes)
+}
+
+/// Returns the path of the cgroup v2 of a process, relative to the cgroup v2 mount, from `/proc/<pid>/cgroup`.
+#[cfg(target_os = "linux")]
+fn unified_cgroup(pid: &str) -> Option<String> {
+    fs::read_to_string(format!("/proc/{}/cgroup", pid))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::to_string)
+}
+
+/// Returns where the cgroup v2 hierarchy is mounted, `/sys/fs/cgroup` or `/sys/fs/cgroup/unified` on hybrid systems.
+#[cfg(target_os = "linux")]
+fn cgroup2_mount() -> Option<PathBuf> {
+    fs::read_to_string("/proc/self/mounts")
+        .ok()?
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.get(2) == Some(&"cgroup2"))
+        .and_then(|fields| fields.get(1).map(PathBuf::from))
+}
+
+/// Freezes or thaws a cgroup, waiting for the freezer to report the new state.
+#[cfg(target_os = "linux")]
+fn freeze(cgroup: &Path, frozen: bool) -> io::Result<()> {
+    fs::write(cgroup.join("cgroup.freeze"), if frozen { "1" } else { "0" })?;
+
+    let expected = if frozen { "frozen 1" } else { "frozen 0" };
+    poll(
+        || {
+            let events = fs::read_to_string(cgroup.join("cgroup.events"))?;
+            Ok(events.lines().any(|line| line == expected))
+        },
+        || format!("{} did not report {}", cgroup.display(), expected),
+    )
+}
+
+/// Polls `done` until it returns true, failing with the `message` after `STATE_TIMEOUT`.
+#[cfg(target_os = "linux")]
+fn poll(
+    mut done: impl FnMut() -> io::Result<bool>,
+    message: impl FnOnce() -> String,
+) -> io::Result<()> {
+    let deadline = Instant::now() + STATE_TIMEOUT;
+    while !done()? {
+        if Instant::now() >= deadline {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, message()));
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    Ok(())
+}
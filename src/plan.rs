@@ -0,0 +1,235 @@
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use crate::dump::DiagnosticDump;
+use crate::{error::Error, guard::ProcessTermination};
+
+/// Trait for user-defined termination steps, such as calling an admin endpoint or running `nginx -s quit`.
+///
+/// It is implemented for closures taking the same arguments as `run`.
+///
+/// A step given a timeout with `TerminationPlan::step_timeout` runs on its own thread. If it did not return once the
+/// timeout elapsed, the plan goes on with the next step as it does when a wait times out, and the step is left running
+/// in the background.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::time::{Duration, Instant};
+/// use proc_guard::{ProcGuard, TerminationPlan};
+///
+/// let plan = TerminationPlan::new()
+///     .step(|pid: u32, _deadline: Option<Instant>| {
+///         println!("Asking {} to stop", pid);
+///         Ok(())
+///     })
+///     .wait_timeout(Duration::from_secs(5))
+///     .kill()
+///     .wait();
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub trait TerminationStep: Send + Sync {
+    /// Performs the step on the process.
+    ///
+    /// # Arguments
+    ///
+    /// * `pid` - The process ID of the process to terminate.
+    /// * `deadline` - The instant at which the plan goes on without the step, `None` if the step is not bounded.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the step was performed, the plan goes on with the next step.
+    /// * `Err(Error::Timeout)` - If the step did not complete before the deadline, the plan escalates to the next step as it does when a wait times out.
+    /// * `Err(Error)` - If the step failed, the termination stops with this error.
+    fn run(&self, pid: u32, deadline: Option<Instant>) -> Result<(), Error>;
+}
+
+impl<F> TerminationStep for F
+where
+    F: Fn(u32, Option<Instant>) -> Result<(), Error> + Send + Sync,
+{
+    fn run(&self, pid: u32, deadline: Option<Instant>) -> Result<(), Error> {
+        self(pid, deadline)
+    }
+}
+
+/// A step of a termination plan.
+#[derive(Clone)]
+pub(crate) enum Step {
+    CtrlC,
+    Kill,
+    CloseStdin,
+    WriteStdin(Vec<u8>),
+    Wait,
+    WaitTimeout(Duration),
+    Custom(Arc<dyn TerminationStep>, Option<Duration>),
+    #[cfg(unix)]
+    Dump(DiagnosticDump),
+}
+
+impl fmt::Debug for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::CtrlC => write!(f, "CtrlC"),
+            Step::Kill => write!(f, "Kill"),
+            Step::CloseStdin => write!(f, "CloseStdin"),
+            Step::WriteStdin(input) => f.debug_tuple("WriteStdin").field(input).finish(),
+            Step::Wait => write!(f, "Wait"),
+            Step::WaitTimeout(timeout) => f.debug_tuple("WaitTimeout").field(timeout).finish(),
+            Step::Custom(_, timeout) => f.debug_tuple("Custom").field(timeout).finish(),
+            #[cfg(unix)]
+            Step::Dump(dump) => f.debug_tuple("Dump").field(dump).finish(),
+        }
+    }
+}
+
+/// An ordered list of termination steps, mixing built-in steps with user-defined ones.
+///
+/// Steps run one after the other:
+/// - A wait that ends because the process exited ends the plan with the exit status of the process.
+/// - A wait that times out, or a user-defined step returning `Error::Timeout` or exceeding its timeout, goes on with the next step.
+///   If it was the last step, the termination fails with `Error::Timeout`.
+/// - A plan whose last step is an action, such as `kill`, ends without exit status.
+/// - On Unix, a dump step ends the plan with the exit status of the process if it exits during the capture window.
+///
+/// Every `ProcessTermination` converts into the equivalent plan.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use std::time::Duration;
+/// use proc_guard::{ProcGuard, TerminationPlan};
+///
+/// let child = if cfg!(target_os = "windows") {
+///     Command::new("timeout").args(["/t", "2"]).spawn()?
+/// } else {
+///     Command::new("sleep").arg("2").spawn()?
+/// };
+///
+/// let plan = TerminationPlan::new()
+///     .ctrl_c()
+///     .wait_timeout(Duration::from_secs(5))
+///     .kill()
+///     .wait();
+/// let guard = ProcGuard::new(child, plan);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TerminationPlan {
+    pub(crate) steps: Vec<Step>,
+}
+
+impl TerminationPlan {
+    /// Creates an empty plan, which leaves the process running.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends a Ctrl+C signal to the process.
+    pub fn ctrl_c(self) -> Self {
+        self.push(Step::CtrlC)
+    }
+
+    /// Kills the process.
+    pub fn kill(self) -> Self {
+        self.push(Step::Kill)
+    }
+
+    /// Closes the stdin of the process.
+    pub fn close_stdin(self) -> Self {
+        self.push(Step::CloseStdin)
+    }
+
+    /// Writes the given bytes, such as a quit command, to the stdin of the process.
+    pub fn write_stdin(self, input: impl Into<Vec<u8>>) -> Self {
+        self.push(Step::WriteStdin(input.into()))
+    }
+
+    /// Waits indefinitely for the process to exit.
+    pub fn wait(self) -> Self {
+        self.push(Step::Wait)
+    }
+
+    /// Waits for a specified duration for the process to exit.
+    pub fn wait_timeout(self, timeout: Duration) -> Self {
+        self.push(Step::WaitTimeout(timeout))
+    }
+
+    /// Runs a user-defined step without deadline.
+    pub fn step(self, step: impl TerminationStep + 'static) -> Self {
+        self.push(Step::Custom(Arc::new(step), None))
+    }
+
+    /// Runs a user-defined step, going on with the next step if it did not return once `timeout` elapsed.
+    pub fn step_timeout(self, step: impl TerminationStep + 'static, timeout: Duration) -> Self {
+        self.push(Step::Custom(Arc::new(step), Some(timeout)))
+    }
+
+    /// Asks the process for diagnostics, such as a thread dump, and records them for the termination outcome.
+    ///
+    /// The guard reads the piped output of the process from the start of the termination, forwarding it if
+    /// `ProcGuard::with_output` was not called, so that the diagnostics can be recorded.
+    #[cfg(unix)]
+    pub fn dump(self, dump: DiagnosticDump) -> Self {
+        self.push(Step::Dump(dump))
+    }
+
+    /// Returns whether the plan has a dump step.
+    #[cfg(unix)]
+    pub(crate) fn dumps(&self) -> bool {
+        self.steps.iter().any(|step| matches!(step, Step::Dump(_)))
+    }
+
+    fn push(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+}
+
+impl From<ProcessTermination> for TerminationPlan {
+    fn from(termination: ProcessTermination) -> Self {
+        let plan = TerminationPlan::new();
+        match termination {
+            ProcessTermination::Wait => plan.wait(),
+            ProcessTermination::WaitTimeout(timeout) => plan.wait_timeout(timeout),
+            ProcessTermination::WaitTimeoutKill(timeout) => plan.wait_timeout(timeout).kill(),
+            ProcessTermination::CtrlC => plan.ctrl_c(),
+            ProcessTermination::CtrlCWait => plan.ctrl_c().wait(),
+            ProcessTermination::CtrlCWaitTimeout(timeout) => plan.ctrl_c().wait_timeout(timeout),
+            ProcessTermination::CtrlCWaitTimeoutKill(timeout) => {
+                plan.ctrl_c().wait_timeout(timeout).kill()
+            }
+            ProcessTermination::Kill => plan.kill(),
+            ProcessTermination::KillWait => plan.kill().wait(),
+            ProcessTermination::CloseStdin => plan.close_stdin(),
+            ProcessTermination::CloseStdinWait => plan.close_stdin().wait(),
+            ProcessTermination::CloseStdinWaitTimeout(timeout) => {
+                plan.close_stdin().wait_timeout(timeout)
+            }
+            ProcessTermination::CloseStdinWaitTimeoutKill(timeout) => {
+                plan.close_stdin().wait_timeout(timeout).kill()
+            }
+            ProcessTermination::WriteStdin(input) => plan.write_stdin(input),
+            ProcessTermination::WriteStdinWait(input) => plan.write_stdin(input).wait(),
+            ProcessTermination::WriteStdinWaitTimeout(input, timeout) => {
+                plan.write_stdin(input).wait_timeout(timeout)
+            }
+            ProcessTermination::WriteStdinWaitTimeoutKill(input, timeout) => {
+                plan.write_stdin(input).wait_timeout(timeout).kill()
+            }
+        }
+    }
+}
@@ -10,6 +10,9 @@ mod imp;
 #[path = "unix.rs"]
 mod imp;
 
+#[cfg(unix)]
+pub(crate) mod sys;
+
 /// Sends a Ctrl+C signal to a process.
 ///
 /// # Parameters
@@ -25,6 +28,7 @@ mod imp;
 /// This function will return an error in the following situations:
 ///
 /// * `proc_guard::Error::FailedToSendCtrlC` - with a system specific error code if the internal OS API failed.
+/// * `proc_guard::Error::PermissionDenied` - On Unix, if the current process is not allowed to signal the process.
 ///
 /// # Platform-specific behavior
 ///
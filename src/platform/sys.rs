@@ -0,0 +1,28 @@
+use std::io;
+
+use libc::c_int;
+
+/// Converts the result of a system call returning -1 on error.
+pub(crate) fn cvt(result: c_int) -> io::Result<c_int> {
+    if result == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+/// Writes `content` to the file at the nul-terminated `path`.
+///
+/// Only system calls are made, so that it can run between `fork` and `exec`.
+#[cfg(target_os = "linux")]
+pub(crate) fn write_file(path: &[u8], content: &[u8]) -> io::Result<()> {
+    let fd = cvt(unsafe { libc::open(path.as_ptr() as *const libc::c_char, libc::O_WRONLY) })?;
+    let written = unsafe { libc::write(fd, content.as_ptr().cast(), content.len()) };
+    let result = match written {
+        -1 => Err(io::Error::last_os_error()),
+        written if written as usize != content.len() => Err(io::ErrorKind::WriteZero.into()),
+        _ => Ok(()),
+    };
+    unsafe { libc::close(fd) };
+    result
+}
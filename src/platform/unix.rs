@@ -1,6 +1,6 @@
 use std::process::Child;
 
-use libc::{kill, pid_t, EIO, SIGINT};
+use libc::{kill, pid_t, EIO, EPERM, SIGINT};
 
 use crate::error::Error;
 
@@ -9,11 +9,10 @@ pub(crate) fn _send_ctrl_c(child: &mut Child) -> Result<(), Error> {
     let result = unsafe { kill(pid, SIGINT) };
 
     if result != 0 {
-        Err(Error::FailedToSendCtrlC(
-            std::io::Error::last_os_error()
-                .raw_os_error()
-                .unwrap_or(EIO) as u32,
-        ))
+        match std::io::Error::last_os_error().raw_os_error() {
+            Some(EPERM) => Err(Error::PermissionDenied(child.id())),
+            code => Err(Error::FailedToSendCtrlC(code.unwrap_or(EIO) as u32)),
+        }
     } else {
         Ok(())
     }
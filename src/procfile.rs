@@ -0,0 +1,468 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use crate::shutdown::ShutdownSignals;
+#[cfg(unix)]
+use crate::usage::ResourceUsage;
+use crate::{
+    error::Error,
+    exit::ExitInfo,
+    guard::{ProcGuard, ProcessTermination},
+    plan::TerminationPlan,
+    router::OutputRouter,
+};
+
+/// Interval at which the runner checks whether a process exited or a shutdown was requested.
+const RUNNER_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Grace period given to the processes by the default termination plan of a runner.
+const DEFAULT_RUNNER_GRACE: Duration = Duration::from_secs(5);
+
+/// A process declared in a Procfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcfileEntry {
+    /// The name of the process.
+    pub name: String,
+    /// The shell command starting the process.
+    pub command: String,
+}
+
+/// The processes of an application, declared as `name: command` lines.
+///
+/// Blank lines and lines starting with `#` are ignored.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use proc_guard::Procfile;
+///
+/// let procfile = Procfile::parse("web: python -m http.server $PORT\nworker: ./worker --queue jobs\n")?;
+/// assert_eq!(procfile.entries[1].name, "worker");
+/// assert_eq!(procfile.entries[1].command, "./worker --queue jobs");
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Procfile {
+    /// The processes, in the order they are declared.
+    pub entries: Vec<ProcfileEntry>,
+}
+
+impl Procfile {
+    /// Parses the content of a Procfile.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::InvalidLine` - If a line is not a `name: command` line, or a name is declared twice.
+    pub fn parse(content: &str) -> Result<Self, Error> {
+        let mut entries: Vec<ProcfileEntry> = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || Error::InvalidLine(index + 1, line.to_string());
+            let (name, command) = trimmed.split_once(':').ok_or_else(invalid)?;
+            let (name, command) = (name.trim(), command.trim());
+            let valid_name = name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+            if name.is_empty()
+                || !valid_name
+                || command.is_empty()
+                || entries.iter().any(|entry| entry.name == name)
+            {
+                return Err(invalid());
+            }
+
+            entries.push(ProcfileEntry {
+                name: name.to_string(),
+                command: command.to_string(),
+            });
+        }
+
+        Ok(Procfile { entries })
+    }
+
+    /// Reads and parses a Procfile.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If the file cannot be read.
+    /// * `proc_guard::Error::InvalidLine` - If a line cannot be parsed.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+}
+
+/// Parses the content of a `.env` file, `KEY=value` lines optionally starting with `export`.
+///
+/// Blank lines and lines starting with `#` are ignored. Values may be enclosed in single quotes, kept verbatim,
+/// or in double quotes, where `\n`, `\"` and `\\` are unescaped.
+///
+/// # Errors
+///
+/// * `proc_guard::Error::InvalidLine` - If a line is not a `KEY=value` line.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let vars = proc_guard::parse_env("# local settings\nexport PORT=8080\nGREETING=\"hello world\"\n")?;
+/// assert_eq!(vars, [
+///     ("PORT".to_string(), "8080".to_string()),
+///     ("GREETING".to_string(), "hello world".to_string()),
+/// ]);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub fn parse_env(content: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut vars = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || Error::InvalidLine(index + 1, line.to_string());
+        let assignment = trimmed
+            .strip_prefix("export ")
+            .map_or(trimmed, str::trim_start);
+        let (key, value) = assignment.split_once('=').ok_or_else(invalid)?;
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+
+        vars.push((key.to_string(), unquote(value.trim()).ok_or_else(invalid)?));
+    }
+
+    Ok(vars)
+}
+
+/// Reads and parses a `.env` file, see `parse_env`.
+///
+/// # Errors
+///
+/// * `proc_guard::Error::Io` - If the file cannot be read.
+/// * `proc_guard::Error::InvalidLine` - If a line cannot be parsed.
+pub fn read_env(path: impl AsRef<Path>) -> Result<Vec<(String, String)>, Error> {
+    parse_env(&fs::read_to_string(path)?)
+}
+
+/// Removes the quotes of a `.env` value, `None` if a quote is not closed.
+fn unquote(value: &str) -> Option<String> {
+    if let Some(quoted) = value.strip_prefix('\'') {
+        return quoted.strip_suffix('\'').map(str::to_string);
+    }
+    if let Some(quoted) = value.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"')?;
+        let mut unquoted = String::with_capacity(quoted.len());
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some('n')) => unquoted.push('\n'),
+                ('\\', Some(escaped @ ('"' | '\\'))) => unquoted.push(escaped),
+                _ => {
+                    unquoted.push(c);
+                    continue;
+                }
+            }
+            chars.next();
+        }
+        return Some(unquoted);
+    }
+
+    // An unquoted value ends at an inline comment
+    let value = match value.find(" #") {
+        Some(comment) => value[..comment].trim_end(),
+        None => value,
+    };
+    Some(value.to_string())
+}
+
+/// Requests a running `ProcfileRunner` to shut its processes down, from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle(Arc<AtomicBool>);
+
+impl ShutdownHandle {
+    /// Requests the shutdown, the runner applies the termination plan to every process.
+    pub fn shutdown(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn requested(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// How a process of a `ProcfileRunner` ended.
+#[derive(Debug)]
+pub struct ProcessExit {
+    /// The name of the process.
+    pub name: String,
+    /// How the process ended, `None` if the termination plan did not wait for it or failed.
+    pub exit: Option<ExitInfo>,
+    /// The wall-clock time from the spawn of the process until it was reaped, `None` if the termination failed.
+    pub lifetime: Option<Duration>,
+    /// The resources used by the process and its reaped descendants, `None` if the termination plan did not wait for
+    /// it or failed.
+    #[cfg(unix)]
+    pub usage: Option<ResourceUsage>,
+    /// Why the process could not be terminated, `None` if the termination succeeded.
+    pub error: Option<Error>,
+}
+
+impl ProcessExit {
+    /// The exit of a process whose termination failed with `error`.
+    fn failed(name: String, error: Error) -> Self {
+        ProcessExit {
+            name,
+            exit: None,
+            lifetime: None,
+            #[cfg(unix)]
+            usage: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// How a `ProcfileRunner` ended.
+#[derive(Debug)]
+pub struct RunnerOutcome {
+    /// The exit code the runner should exit with: the shell code of the first process that failed, in the order the
+    /// processes ended, 0 if none failed. A process ended by a step of the termination plan did not fail.
+    pub exit_code: i32,
+    /// The name of the process whose exit shut the others down, `None` if the shutdown was requested.
+    pub trigger: Option<String>,
+    /// How every process ended, in the order of the Procfile.
+    pub exits: Vec<ProcessExit>,
+}
+
+/// Runs the processes of a Procfile, each under a `ProcGuard`, like `foreman start` does.
+///
+/// The output of the processes is multiplexed through an `OutputRouter`. When any process exits, when a shutdown is
+/// requested through a `ShutdownHandle`, or, on Unix, when the current process receives `SIGINT` or `SIGTERM`,
+/// the termination plan is applied to every process still running.
+///
+/// Commands are run by `sh -c`, or `cmd /C` on Windows, the signals of the plan being sent to the shell. A command
+/// starting a long-running program should `exec` it, such as `web: exec ./server`, for the signals to reach it.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::time::Duration;
+/// use proc_guard::{Procfile, ProcfileRunner, ProcessTermination};
+///
+/// let outcome = ProcfileRunner::new(Procfile::read("Procfile")?)
+///     .env_file(".env")?
+///     .termination(ProcessTermination::CtrlCWaitTimeoutKill(Duration::from_secs(10)))
+///     .run()?;
+/// std::process::exit(outcome.exit_code);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ProcfileRunner {
+    procfile: Procfile,
+    env: Vec<(String, String)>,
+    termination: TerminationPlan,
+    router: Option<OutputRouter>,
+    current_dir: Option<PathBuf>,
+    shutdown: ShutdownHandle,
+}
+
+impl ProcfileRunner {
+    /// Creates a runner for the processes of `procfile`.
+    ///
+    /// By default the processes get a Ctrl+C and 5 seconds to exit before being killed, and their output is routed
+    /// to the stdout of the current process.
+    pub fn new(procfile: Procfile) -> Self {
+        ProcfileRunner {
+            procfile,
+            env: Vec::new(),
+            termination: ProcessTermination::CtrlCWaitTimeoutKill(DEFAULT_RUNNER_GRACE).into(),
+            router: None,
+            current_dir: None,
+            shutdown: ShutdownHandle::default(),
+        }
+    }
+
+    /// Adds environment variables to every process.
+    pub fn env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env.extend(vars);
+        self
+    }
+
+    /// Adds the environment variables of a `.env` file to every process.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If the file cannot be read.
+    /// * `proc_guard::Error::InvalidLine` - If a line cannot be parsed.
+    pub fn env_file(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(self.env(read_env(path)?))
+    }
+
+    /// Sets the termination plan applied to every process on shutdown.
+    ///
+    /// The plan should end with a wait, the exit of a process being unknown otherwise.
+    pub fn termination(mut self, termination: impl Into<TerminationPlan>) -> Self {
+        self.termination = termination.into();
+        self
+    }
+
+    /// Sets the router multiplexing the output of the processes.
+    pub fn router(mut self, router: OutputRouter) -> Self {
+        self.router = Some(router);
+        self
+    }
+
+    /// Sets the working directory of the processes.
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Returns a handle requesting the runner to shut its processes down.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Starts every process and waits until one of them exits or a shutdown is requested, then shuts them all down.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If a process cannot be started, the processes already started are shut down.
+    pub fn run(self) -> Result<RunnerOutcome, Error> {
+        let router = self.router.clone().unwrap_or_else(OutputRouter::stdout);
+        #[cfg(unix)]
+        let signals = ShutdownSignals::install(&[libc::SIGINT, libc::SIGTERM]);
+
+        let mut guards = Vec::with_capacity(self.procfile.entries.len());
+        for entry in &self.procfile.entries {
+            let guard = ProcGuard::spawn(&mut self.command(entry), self.termination.clone())?
+                .with_output_router(&router, entry.name.as_str());
+            guards.push((entry.name.clone(), guard));
+        }
+
+        // The processes that exited before the shutdown, in the order of the Procfile when seen at once
+        let mut exited = Vec::new();
+        while exited.is_empty() && !self.shutdown.requested() {
+            #[cfg(unix)]
+            if signals.received() {
+                break;
+            }
+
+            for (index, (_, guard)) in guards.iter_mut().enumerate() {
+                // The guard reaps the process itself, collecting its resource usage
+                if guard.has_exited()? {
+                    exited.push(index);
+                }
+            }
+            if exited.is_empty() {
+                thread::sleep(RUNNER_CHECK_INTERVAL);
+            }
+        }
+        let trigger = exited.first().map(|&index| guards[index].0.clone());
+
+        // The grace periods of the processes run concurrently
+        let ended: Vec<(ProcessExit, Instant)> = guards
+            .into_iter()
+            .map(|(name, mut guard)| {
+                let thread_name = name.clone();
+                let handle = thread::spawn(move || {
+                    // A process that already exited is only released, its status being known
+                    let outcome = match guard.has_exited() {
+                        Ok(true) => guard.supervise(),
+                        _ => guard.terminate_with_output(),
+                    };
+                    let exit = match outcome {
+                        Ok(outcome) => ProcessExit {
+                            name: thread_name,
+                            exit: outcome.exit,
+                            lifetime: Some(outcome.lifetime),
+                            #[cfg(unix)]
+                            usage: outcome.usage,
+                            error: None,
+                        },
+                        Err(error) => ProcessExit::failed(thread_name, error),
+                    };
+                    (exit, Instant::now())
+                });
+                (name, handle)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|(name, handle)| {
+                handle.join().unwrap_or_else(|_| {
+                    let error = io::Error::other(format!("the termination of {} panicked", name));
+                    (ProcessExit::failed(name, error.into()), Instant::now())
+                })
+            })
+            .collect();
+        router.flush();
+
+        // The processes that exited before the shutdown come first, then the others in the order they ended
+        let mut order: Vec<usize> = (0..ended.len()).collect();
+        order.sort_by_key(
+            |&index| match exited.iter().position(|&exited| exited == index) {
+                Some(position) => (0, position, None),
+                None => (1, 0, Some(ended[index].1)),
+            },
+        );
+        let exit_code = order
+            .into_iter()
+            .filter_map(|index| ended[index].0.exit.as_ref())
+            .find(|exit| !exit.success() && !exit.ended_by_guard())
+            .map_or(0, ExitInfo::shell_code);
+        let exits = ended.into_iter().map(|(exit, _)| exit).collect();
+        Ok(RunnerOutcome {
+            exit_code,
+            trigger,
+            exits,
+        })
+    }
+
+    /// Builds the command of a process, run by the shell like in a Procfile.
+    fn command(&self, entry: &ProcfileEntry) -> Command {
+        let mut command = if cfg!(target_os = "windows") {
+            let mut command = Command::new("cmd");
+            command.args(["/C", &entry.command]);
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.args(["-c", &entry.command]);
+            command
+        };
+
+        command
+            .envs(self.env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        command
+    }
+}
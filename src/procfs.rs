@@ -0,0 +1,135 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::Path,
+};
+
+use libc::{c_int, c_long, pid_t};
+
+/// The fields of `/proc/<pid>/stat` telling where a process sits in the process tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ProcStat {
+    pub(crate) pid: u32,
+    pub(crate) ppid: u32,
+    /// The state, such as `R` for running or `Z` for a zombie.
+    pub(crate) state: char,
+    /// The time the process started after the boot, in clock ticks.
+    /// A pid and its start time identify a process, pids being reused.
+    pub(crate) start_time: u64,
+}
+
+impl ProcStat {
+    /// Reads `/proc/<pid>/stat`, `None` if the process does not exist anymore.
+    pub(crate) fn read(pid: u32) -> Option<Self> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The command name may contain spaces and parentheses, the fields start after the last ')'
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+
+        Some(ProcStat {
+            pid,
+            state: fields.first()?.chars().next()?,
+            ppid: fields.get(1)?.parse().ok()?,
+            start_time: fields.get(19)?.parse().ok()?,
+        })
+    }
+
+    /// Sends `signal` to the process unless it exited and its pid was reused.
+    ///
+    /// The pid is pinned by a pidfd while its start time is checked, the signal never reaches another process.
+    /// Without pidfds, before Linux 5.3, the start time is checked right before `kill`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - If the signal was sent.
+    /// * `Ok(false)` - If the process does not exist anymore.
+    /// * `Err(io::Error)` - If the signal could not be sent.
+    pub(crate) fn signal(&self, signal: c_int) -> io::Result<bool> {
+        let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, self.pid as pid_t, 0) };
+        if pidfd < 0 {
+            let error = io::Error::last_os_error();
+            return match error.raw_os_error() {
+                Some(libc::ESRCH) => Ok(false),
+                Some(libc::ENOSYS) if self.is_alive() => {
+                    result(unsafe { libc::kill(self.pid as pid_t, signal) as c_long })
+                }
+                Some(libc::ENOSYS) => Ok(false),
+                _ => Err(error),
+            };
+        }
+
+        let sent = if self.is_alive() {
+            result(unsafe {
+                libc::syscall(
+                    libc::SYS_pidfd_send_signal,
+                    pidfd as c_int,
+                    signal,
+                    std::ptr::null::<libc::siginfo_t>(),
+                    0,
+                )
+            })
+        } else {
+            Ok(false)
+        };
+        unsafe { libc::close(pidfd as c_int) };
+        sent
+    }
+
+    /// Returns whether the pid still identifies this process.
+    fn is_alive(&self) -> bool {
+        ProcStat::read(self.pid).map(|stat| stat.start_time) == Some(self.start_time)
+    }
+}
+
+/// Converts the result of a system call sending a signal, a process that exited meanwhile not being an error.
+fn result(code: c_long) -> io::Result<bool> {
+    if code == 0 {
+        return Ok(true);
+    }
+    let error = io::Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::ESRCH) => Ok(false),
+        _ => Err(error),
+    }
+}
+
+/// Returns the entries of a directory whose name is a number, such as the processes of `/proc`.
+pub(crate) fn numeric_entries(dir: &Path) -> Vec<u32> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the processes that could be read from `/proc`.
+pub(crate) fn processes() -> Vec<ProcStat> {
+    numeric_entries(Path::new("/proc"))
+        .into_iter()
+        .filter_map(ProcStat::read)
+        .collect()
+}
+
+/// Returns the descendants of a process, parents before their children, by scanning the parent of every process in `/proc`.
+pub(crate) fn descendants(pid: u32) -> Vec<ProcStat> {
+    tree(&processes(), pid)
+}
+
+/// Returns the descendants of a process among `processes`, parents before their children.
+pub(crate) fn tree(processes: &[ProcStat], pid: u32) -> Vec<ProcStat> {
+    let mut children: HashMap<u32, Vec<&ProcStat>> = HashMap::new();
+    for process in processes {
+        children.entry(process.ppid).or_default().push(process);
+    }
+
+    let mut descendants = Vec::new();
+    let mut queue = VecDeque::from([pid]);
+    while let Some(parent) = queue.pop_front() {
+        for &child in children.get(&parent).into_iter().flatten() {
+            descendants.push(*child);
+            queue.push_back(child.pid);
+        }
+    }
+    descendants
+}
@@ -0,0 +1,114 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    process::{Command, Stdio},
+    ptr,
+};
+
+use libc::EIO;
+
+use crate::error::Error;
+
+/// The character sent by a terminal when Ctrl+C is pressed.
+const CTRL_C: u8 = 0x03;
+
+/// The character sent by a terminal when Ctrl+D is pressed.
+const CTRL_D: u8 = 0x04;
+
+/// A pseudo-terminal whose slave side becomes the controlling terminal of a child process.
+#[derive(Debug)]
+pub(crate) struct Pty {
+    master: File,
+    slave: OwnedFd,
+}
+
+impl Pty {
+    /// Allocates a new pseudo-terminal.
+    pub(crate) fn open() -> io::Result<Self> {
+        let mut master = -1;
+        let mut slave = -1;
+        let result = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                ptr::null_mut(),
+                ptr::null(),
+                ptr::null(),
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let (master, slave) = unsafe { (File::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+        // Only the slave side must be inherited by the child
+        for fd in [master.as_raw_fd(), slave.as_raw_fd()] {
+            if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(Pty { master, slave })
+    }
+
+    /// Makes the slave side the stdin, stdout, stderr and controlling terminal of the process spawned by `command`,
+    /// the process becoming the leader of a new session.
+    pub(crate) fn configure(&self, command: &mut Command) -> io::Result<()> {
+        command
+            .stdin(Stdio::from(self.slave.try_clone()?))
+            .stdout(Stdio::from(self.slave.try_clone()?))
+            .stderr(Stdio::from(self.slave.try_clone()?));
+
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                // stdin is the slave side at this point
+                if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Releases the slave side, keeping the master side.
+    pub(crate) fn into_master(self) -> File {
+        self.master
+    }
+}
+
+/// Writes the Ctrl+C character to the master side of a pseudo-terminal.
+/// The line discipline sends `SIGINT` to the whole foreground process group of the terminal.
+pub(crate) fn send_ctrl_c(mut master: &File) -> Result<(), Error> {
+    master
+        .write_all(&[CTRL_C])
+        .map_err(|e| Error::FailedToSendCtrlC(e.raw_os_error().unwrap_or(EIO) as u32))
+}
+
+/// Writes the end-of-file character to the master side of a pseudo-terminal.
+/// A process reading the terminal in canonical mode reads the end of the file if the current line is empty.
+pub(crate) fn send_eof(mut master: &File) -> Result<(), Error> {
+    Ok(master.write_all(&[CTRL_D])?)
+}
+
+/// Copies the window size of the terminal of the current process, read from its stdin, to a pseudo-terminal.
+/// The kernel sends `SIGWINCH` to the foreground process group of the pseudo-terminal when its size changes.
+pub(crate) fn copy_window_size(master: &File) -> io::Result<()> {
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDIN_FILENO, libc::TIOCGWINSZ as _, &mut size) == -1
+            || libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ as _, &size) == -1
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::{fmt, io, os::unix::process::CommandExt, process::Command, time::Duration};
+
+use libc::rlim_t;
+
+/// Kernel resource limits applied to a process before it runs its command, see `SpawnOptions::rlimits`.
+///
+/// Unlike `ResourceLimits`, which samples a running process, these limits are enforced by the kernel: allocations
+/// beyond the address space limit fail, opening a file beyond the open files limit fails with `EMFILE`, and a process
+/// exceeding its CPU time or file size limit receives `SIGXCPU` or `SIGXFSZ`, which `ExitInfo::exceeded_rlimit`
+/// reports. The limits are inherited by the descendants of the process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rlimits {
+    address_space: Option<u64>,
+    cpu_time: Option<Duration>,
+    open_files: Option<u64>,
+    core_size: Option<u64>,
+    processes: Option<u64>,
+    file_size: Option<u64>,
+}
+
+/// A kernel resource limit, see `Rlimits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rlimit {
+    /// The size of the virtual memory, `RLIMIT_AS`.
+    AddressSpace,
+    /// The CPU time, `RLIMIT_CPU`.
+    CpuTime,
+    /// The number of open files, `RLIMIT_NOFILE`.
+    OpenFiles,
+    /// The size of a core dump, `RLIMIT_CORE`.
+    CoreSize,
+    /// The number of processes of the user, `RLIMIT_NPROC`.
+    Processes,
+    /// The size of a written file, `RLIMIT_FSIZE`.
+    FileSize,
+}
+
+impl Rlimit {
+    /// Returns the limit whose violation sends `signal`, `SIGXCPU` or `SIGXFSZ`.
+    pub(crate) fn from_signal(signal: i32) -> Option<Self> {
+        match signal {
+            libc::SIGXCPU => Some(Rlimit::CpuTime),
+            libc::SIGXFSZ => Some(Rlimit::FileSize),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Rlimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rlimit::AddressSpace => "address space",
+            Rlimit::CpuTime => "CPU time",
+            Rlimit::OpenFiles => "open files",
+            Rlimit::CoreSize => "core size",
+            Rlimit::Processes => "process",
+            Rlimit::FileSize => "file size",
+        };
+        write!(f, "{} limit", name)
+    }
+}
+
+impl Rlimits {
+    /// Creates a set of limits where nothing is limited beyond the limits of the current process.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the virtual memory of the process, in bytes.
+    pub fn address_space(mut self, bytes: u64) -> Self {
+        self.address_space = Some(bytes);
+        self
+    }
+
+    /// Limits the CPU time of the process, rounded up to the second.
+    ///
+    /// The process receives `SIGXCPU` once it used `time`, and `SIGKILL` a second later if it handles `SIGXCPU`.
+    pub fn cpu_time(mut self, time: Duration) -> Self {
+        self.cpu_time = Some(time);
+        self
+    }
+
+    /// Limits the number of files the process can open, the highest file descriptor being `count - 1`.
+    pub fn open_files(mut self, count: u64) -> Self {
+        self.open_files = Some(count);
+        self
+    }
+
+    /// Limits the size of the core dumps of the process, in bytes, 0 disabling them.
+    pub fn core_size(mut self, bytes: u64) -> Self {
+        self.core_size = Some(bytes);
+        self
+    }
+
+    /// Limits the number of processes of the real user of the process, which includes the processes the user runs
+    /// outside the guard.
+    pub fn processes(mut self, count: u64) -> Self {
+        self.processes = Some(count);
+        self
+    }
+
+    /// Limits the size of the files the process writes, in bytes.
+    pub fn file_size(mut self, bytes: u64) -> Self {
+        self.file_size = Some(bytes);
+        self
+    }
+
+    /// Applies the limits in the process spawned by `command`, before it runs the command.
+    pub(crate) fn configure(self, command: &mut Command) {
+        unsafe {
+            command.pre_exec(move || self.apply());
+        }
+    }
+
+    /// Sets the limits of the current process.
+    fn apply(&self) -> io::Result<()> {
+        // The hard CPU limit sends SIGKILL, the soft one SIGXCPU only if it is reached first
+        let cpu_time = self.cpu_time.map(|time| {
+            let seconds = time.as_secs() + u64::from(time.subsec_nanos() > 0);
+            (seconds, seconds + 1)
+        });
+        let same = |limit: Option<u64>| limit.map(|limit| (limit, limit));
+
+        let limits = [
+            (libc::RLIMIT_AS, same(self.address_space)),
+            (libc::RLIMIT_CPU, cpu_time),
+            (libc::RLIMIT_NOFILE, same(self.open_files)),
+            (libc::RLIMIT_CORE, same(self.core_size)),
+            (libc::RLIMIT_NPROC, same(self.processes)),
+            (libc::RLIMIT_FSIZE, same(self.file_size)),
+        ];
+        for (resource, limit) in limits {
+            if let Some((soft, hard)) = limit {
+                let limit = libc::rlimit {
+                    rlim_cur: soft as rlim_t,
+                    rlim_max: hard as rlim_t,
+                };
+                if unsafe { libc::setrlimit(resource, &limit) } == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    sync::{mpsc, Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::output::{LineSplitter, OutputLine, OutputStream};
+
+/// Colours given to the prefixes of the routed processes, in registration order.
+const COLORS: [&str; 6] = [
+    "\x1b[36m", "\x1b[33m", "\x1b[32m", "\x1b[35m", "\x1b[34m", "\x1b[31m",
+];
+
+/// Resets the colour of the terminal.
+const RESET: &str = "\x1b[0m";
+
+/// Maximum length kept of a routed line that is not terminated yet.
+const MAX_ROUTED_LINE: usize = 64 * 1024;
+
+/// Maximum number of chunks waiting to be written to the sink, the routes block once it is reached.
+const MAX_QUEUED_CHUNKS: usize = 1024;
+
+/// Multiplexes the output of many guarded processes into a single sink, one line at a time, each line being prefixed
+/// with the name of its process, like `foreman` or `docker compose` do.
+///
+/// Names are padded to the longest name registered so far, so that the lines stay aligned.
+/// Lines are written by a background thread, so that a slow sink does not block the processes right away. Once
+/// `MAX_QUEUED_CHUNKS` chunks wait for the sink, the output of the processes stops being read until the sink catches
+/// up, so that the memory does not grow without limit.
+/// A router is cheap to clone, clones share the same sink.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::{Command, Stdio};
+/// use proc_guard::{OutputRouter, ProcGuard, ProcessTermination};
+///
+/// let router = OutputRouter::new(std::io::stdout()).timestamps(true).colors(true);
+///
+/// let mut web = if cfg!(target_os = "windows") {
+///     let mut command = Command::new("cmd");
+///     command.args(["/C", "echo listening"]);
+///     command
+/// } else {
+///     let mut command = Command::new("echo");
+///     command.arg("listening");
+///     command
+/// };
+/// let web = ProcGuard::spawn(web.stdout(Stdio::piped()), ProcessTermination::Wait)?
+///     .with_output_router(&router, "web");
+///
+/// web.terminate()?;
+/// router.flush();
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OutputRouter {
+    shared: Arc<RouterShared>,
+    sender: mpsc::SyncSender<Message>,
+}
+
+#[derive(Debug)]
+struct RouterShared {
+    settings: Mutex<Settings>,
+    sources: Mutex<Vec<Source>>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Settings {
+    timestamps: bool,
+    colors: bool,
+}
+
+/// A process whose output is routed.
+#[derive(Debug)]
+struct Source {
+    name: String,
+    color: &'static str,
+}
+
+#[derive(Debug)]
+enum Message {
+    Chunk(usize, OutputStream, SystemTime, Vec<u8>),
+    End(usize, OutputStream),
+    Flush(mpsc::Sender<()>),
+}
+
+impl OutputRouter {
+    /// Creates a router writing to `sink`, without timestamps nor colours.
+    ///
+    /// Errors writing to the sink are ignored, the processes must keep being drained.
+    pub fn new(sink: impl Write + Send + 'static) -> Self {
+        let shared = Arc::new(RouterShared {
+            settings: Mutex::new(Settings::default()),
+            sources: Mutex::new(Vec::new()),
+        });
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_CHUNKS);
+
+        let writer_shared = shared.clone();
+        thread::spawn(move || write_lines(sink, receiver, &writer_shared));
+
+        OutputRouter { shared, sender }
+    }
+
+    /// Creates a router writing to the stdout of the current process.
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+
+    /// Prefixes every line with the UTC time it was read at, as `HH:MM:SS.mmm`.
+    pub fn timestamps(self, timestamps: bool) -> Self {
+        self.shared.settings().timestamps = timestamps;
+        self
+    }
+
+    /// Colours the prefix of every line with ANSI escape codes, each process getting its own colour.
+    pub fn colors(self, colors: bool) -> Self {
+        self.shared.settings().colors = colors;
+        self
+    }
+
+    /// Blocks until every line read so far is written to the sink, and flushes the sink.
+    pub fn flush(&self) {
+        let (sender, receiver) = mpsc::channel();
+        if self.sender.send(Message::Flush(sender)).is_ok() {
+            let _ = receiver.recv();
+        }
+    }
+
+    /// Registers a process named `name` and returns the route its output is sent through.
+    pub(crate) fn route(&self, name: impl Into<String>) -> Route {
+        let mut sources = self.shared.sources();
+        let source = sources.len();
+        sources.push(Source {
+            name: name.into(),
+            color: COLORS[source % COLORS.len()],
+        });
+
+        Route {
+            source,
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl RouterShared {
+    fn settings(&self) -> MutexGuard<'_, Settings> {
+        self.settings.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn sources(&self) -> MutexGuard<'_, Vec<Source>> {
+        self.sources.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sends the output of one process to a router.
+#[derive(Debug, Clone)]
+pub(crate) struct Route {
+    source: usize,
+    sender: mpsc::SyncSender<Message>,
+}
+
+impl Route {
+    /// Sends a chunk read from `stream`, blocking while `MAX_QUEUED_CHUNKS` chunks wait for the sink.
+    pub(crate) fn chunk(&self, stream: OutputStream, chunk: &[u8]) {
+        let _ = self.sender.send(Message::Chunk(
+            self.source,
+            stream,
+            SystemTime::now(),
+            chunk.to_vec(),
+        ));
+    }
+
+    /// Tells that `stream` reached its end, so that its last line is written even if it is not terminated.
+    pub(crate) fn end(&self, stream: OutputStream) {
+        let _ = self.sender.send(Message::End(self.source, stream));
+    }
+}
+
+/// Writes the routed lines until every sender is dropped.
+fn write_lines(mut sink: impl Write, receiver: mpsc::Receiver<Message>, shared: &RouterShared) {
+    let mut splitters: HashMap<usize, LineSplitter> = HashMap::new();
+
+    for message in receiver {
+        match message {
+            Message::Chunk(source, stream, time, chunk) => {
+                let splitter = splitters
+                    .entry(source)
+                    .or_insert_with(|| LineSplitter::new(MAX_ROUTED_LINE));
+                splitter.push(stream, &chunk, |line| {
+                    write_line(&mut sink, shared, source, Some(time), &line)
+                });
+            }
+            Message::End(source, stream) => {
+                if let Some(splitter) = splitters.get_mut(&source) {
+                    splitter.flush(stream, |line| {
+                        write_line(&mut sink, shared, source, None, &line)
+                    });
+                }
+            }
+            Message::Flush(done) => {
+                let _ = sink.flush();
+                let _ = done.send(());
+            }
+        }
+    }
+
+    let _ = sink.flush();
+}
+
+/// Writes a line prefixed with the name of its process, padded to the longest name.
+fn write_line(
+    sink: &mut impl Write,
+    shared: &RouterShared,
+    source: usize,
+    time: Option<SystemTime>,
+    line: &OutputLine,
+) {
+    let settings = *shared.settings();
+    let mut prefix = String::new();
+    {
+        let sources = shared.sources();
+        let width = sources.iter().map(|source| source.name.len()).max();
+        let source = &sources[source];
+
+        if settings.colors {
+            prefix.push_str(source.color);
+        }
+        if settings.timestamps {
+            prefix.push_str(&format_time(time.unwrap_or_else(SystemTime::now)));
+            prefix.push(' ');
+        }
+        prefix.push_str(&format!(
+            "{:width$} |",
+            source.name,
+            width = width.unwrap_or(0)
+        ));
+        if settings.colors {
+            prefix.push_str(RESET);
+        }
+    }
+
+    // Errors are ignored, the processes must keep being drained
+    let _ = writeln!(sink, "{} {}", prefix, line.text);
+}
+
+/// Formats the UTC time of the day as `HH:MM:SS.mmm`.
+fn format_time(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() % 86400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
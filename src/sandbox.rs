@@ -0,0 +1,462 @@
+use std::{
+    ffi::CString,
+    io, mem,
+    os::unix::{ffi::OsStrExt, process::CommandExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use libc::{c_int, c_long, sock_filter, sock_fprog};
+
+use crate::platform::sys::cvt;
+
+/// `AUDIT_ARCH` of the syscalls a filter accepts, the syscall numbers being specific to the architecture.
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+/// The bit of the x32 syscalls, which share the architecture of x86_64 but not its syscall numbers.
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+/// Offsets of the syscall number, the architecture and the low half of the first argument in `seccomp_data`.
+const SYSCALL_NUMBER: u32 = 0;
+const SYSCALL_ARCH: u32 = 4;
+const SYSCALL_ARG0: u32 = 16;
+
+/// `LANDLOCK_CREATE_RULESET_VERSION`, to query the Landlock ABI.
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+
+/// `LANDLOCK_RULE_PATH_BENEATH`.
+const LANDLOCK_RULE_PATH_BENEATH: c_int = 1;
+
+/// Landlock filesystem access rights.
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+/// The rights of the first ABI, from `EXECUTE` to `MAKE_SYM`.
+const ACCESS_FS_ABI_1: u64 = (1 << 13) - 1;
+const ACCESS_FS_REFER: u64 = 1 << 13;
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+/// The rights that apply to a file, the others apply to the content of a directory.
+const ACCESS_FS_FILE: u64 = ACCESS_FS_EXECUTE
+    | ACCESS_FS_WRITE_FILE
+    | ACCESS_FS_READ_FILE
+    | ACCESS_FS_TRUNCATE
+    | ACCESS_FS_IOCTL_DEV;
+
+/// A sandbox applied to a process before it runs its command, see `SpawnOptions::sandbox`.
+///
+/// The process, and every process it starts, cannot gain privileges anymore through setuid executables or file
+/// capabilities (`PR_SET_NO_NEW_PRIVS`). On top of that, a seccomp filter denies the syscalls of the presets and of the
+/// custom list, and Landlock restricts filesystem access to the allowed paths once one is allowed.
+///
+/// By default, a denied syscall kills the process with `SIGSYS`, which `ExitInfo::violated_seccomp` recognizes.
+/// A path outside the allowed ones fails with `EACCES`, like a path the process has no permission on.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use proc_guard::{ProcGuard, ProcessTermination, Sandbox, SeccompPreset, SpawnOptions};
+///
+/// let mut command = Command::new("sh");
+/// command.args(["-c", "sleep 0"]);
+/// let guard = ProcGuard::spawn_with(
+///     &mut command,
+///     ProcessTermination::KillWait,
+///     SpawnOptions::new().sandbox(Sandbox::new().seccomp(SeccompPreset::NoFork)),
+/// )?;
+/// // The shell forks to run `sleep`
+/// let exit = guard.supervise()?.exit.expect("No exit info");
+/// assert!(exit.violated_seccomp());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sandbox {
+    presets: Vec<SeccompPreset>,
+    denied_syscalls: Vec<c_long>,
+    action: SeccompAction,
+    paths: Vec<(PathBuf, PathAccess)>,
+}
+
+/// A set of syscalls denied by the seccomp filter of a `Sandbox`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeccompPreset {
+    /// Denies sockets other than Unix sockets, and `io_uring` which creates sockets without the `socket` syscall.
+    NoNetwork,
+    /// Denies tracing other processes and accessing their memory.
+    NoPtrace,
+    /// Denies creating processes, threads are still allowed.
+    NoFork,
+}
+
+/// What the seccomp filter of a `Sandbox` does with a denied syscall.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeccompAction {
+    /// Kills the process with `SIGSYS`.
+    #[default]
+    KillProcess,
+    /// Fails the syscall with the given errno, such as `libc::EPERM`, the process going on.
+    Errno(i32),
+}
+
+/// The access a `Sandbox` allows to a path and, for a directory, to everything beneath it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathAccess {
+    /// Reading, listing and executing.
+    ReadOnly,
+    /// Every access, including creating, writing, renaming and removing.
+    ReadWrite,
+}
+
+/// A rule of a seccomp filter, matching a syscall by its number.
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// Denies the syscall.
+    Deny(c_long),
+    /// Denies the syscall unless its first argument is the given value.
+    DenyUnlessArg0(c_long, u32),
+    /// Denies the syscall unless its first argument has the given bits.
+    DenyUnlessArg0Has(c_long, u32),
+    /// Fails the syscall with the given errno whatever the action, for syscalls a process falls back from.
+    Fail(c_long, c_int),
+}
+
+impl SeccompPreset {
+    /// Returns the rules of the preset.
+    fn rules(&self) -> Vec<Rule> {
+        match self {
+            SeccompPreset::NoNetwork => vec![
+                Rule::DenyUnlessArg0(libc::SYS_socket, libc::AF_UNIX as u32),
+                Rule::Fail(libc::SYS_io_uring_setup, libc::ENOSYS),
+            ],
+            SeccompPreset::NoPtrace => vec![
+                Rule::Deny(libc::SYS_ptrace),
+                Rule::Deny(libc::SYS_process_vm_readv),
+                Rule::Deny(libc::SYS_process_vm_writev),
+            ],
+            SeccompPreset::NoFork => vec![
+                Rule::DenyUnlessArg0Has(libc::SYS_clone, libc::CLONE_THREAD as u32),
+                // The arguments of clone3 are behind a pointer, the C library falls back to clone
+                Rule::Fail(libc::SYS_clone3, libc::ENOSYS),
+                #[cfg(target_arch = "x86_64")]
+                Rule::Deny(libc::SYS_fork),
+                #[cfg(target_arch = "x86_64")]
+                Rule::Deny(libc::SYS_vfork),
+            ],
+        }
+    }
+}
+
+impl SeccompAction {
+    /// Returns the value a seccomp filter returns for the action.
+    fn value(&self) -> u32 {
+        match *self {
+            SeccompAction::KillProcess => libc::SECCOMP_RET_KILL_PROCESS,
+            SeccompAction::Errno(errno) => {
+                libc::SECCOMP_RET_ERRNO | (errno as u32 & libc::SECCOMP_RET_DATA)
+            }
+        }
+    }
+}
+
+impl Sandbox {
+    /// Creates a sandbox that only sets `PR_SET_NO_NEW_PRIVS`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Denies the syscalls of a preset.
+    pub fn seccomp(mut self, preset: SeccompPreset) -> Self {
+        self.presets.push(preset);
+        self
+    }
+
+    /// Denies the given syscalls, numbered like `libc::SYS_*` for the current architecture.
+    pub fn deny_syscalls(mut self, syscalls: impl IntoIterator<Item = c_long>) -> Self {
+        self.denied_syscalls.extend(syscalls);
+        self
+    }
+
+    /// Sets what happens when the process makes a denied syscall, it is killed by default.
+    pub fn seccomp_action(mut self, action: SeccompAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Allows `access` to `path` and, for a directory, to everything beneath it, restricting filesystem access to the
+    /// allowed paths.
+    ///
+    /// The command and the libraries it loads must be allowed too, such as `/usr` read-only.
+    pub fn allow_path(mut self, path: impl AsRef<Path>, access: PathAccess) -> Self {
+        self.paths.push((path.as_ref().to_path_buf(), access));
+        self
+    }
+
+    /// Applies the sandbox in the process spawned by `command`, before it runs the command.
+    ///
+    /// # Errors
+    ///
+    /// * `io::ErrorKind::Unsupported` - If syscalls are denied on an architecture other than x86_64 and aarch64, or if
+    ///   paths are allowed and the kernel does not support Landlock.
+    /// * `io::ErrorKind::InvalidInput` - If an allowed path contains a nul byte.
+    pub(crate) fn configure(&self, command: &mut Command) -> io::Result<()> {
+        // Nothing can be allocated between the fork and the exec
+        let filter = self.filter()?;
+        let landlock = if self.paths.is_empty() {
+            None
+        } else {
+            Some(self.landlock()?)
+        };
+
+        unsafe {
+            command.pre_exec(move || {
+                cvt(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+                // Before the filter, which may deny the syscalls of Landlock
+                if let Some(landlock) = &landlock {
+                    landlock.restrict_self()?;
+                }
+                if let Some(filter) = &filter {
+                    let program = sock_fprog {
+                        len: filter.len() as u16,
+                        filter: filter.as_ptr() as *mut sock_filter,
+                    };
+                    let set = libc::syscall(
+                        libc::SYS_seccomp,
+                        libc::SECCOMP_SET_MODE_FILTER,
+                        0,
+                        &program,
+                    );
+                    cvt(set as c_int)?;
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    /// Compiles the seccomp filter, `None` if no syscall is denied.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn filter(&self) -> io::Result<Option<Vec<sock_filter>>> {
+        let rules: Vec<Rule> = self
+            .presets
+            .iter()
+            .flat_map(SeccompPreset::rules)
+            .chain(self.denied_syscalls.iter().copied().map(Rule::Deny))
+            .collect();
+        if rules.is_empty() {
+            return Ok(None);
+        }
+        let action = self.action.value();
+
+        let mut filter = vec![
+            load(SYSCALL_ARCH),
+            jump(libc::BPF_JEQ, AUDIT_ARCH, 1, 0),
+            ret(libc::SECCOMP_RET_KILL_PROCESS),
+        ];
+        #[cfg(target_arch = "x86_64")]
+        filter.extend([
+            load(SYSCALL_NUMBER),
+            jump(libc::BPF_JSET, X32_SYSCALL_BIT, 0, 1),
+            ret(action),
+        ]);
+        // Each rule loads what it checks, and falls through to the next one when it does not match
+        for rule in rules {
+            match rule {
+                Rule::Deny(syscall) => filter.extend([
+                    load(SYSCALL_NUMBER),
+                    jump(libc::BPF_JEQ, syscall as u32, 0, 1),
+                    ret(action),
+                ]),
+                Rule::DenyUnlessArg0(syscall, value) => filter.extend([
+                    load(SYSCALL_NUMBER),
+                    jump(libc::BPF_JEQ, syscall as u32, 0, 3),
+                    load(SYSCALL_ARG0),
+                    jump(libc::BPF_JEQ, value, 1, 0),
+                    ret(action),
+                ]),
+                Rule::DenyUnlessArg0Has(syscall, bits) => filter.extend([
+                    load(SYSCALL_NUMBER),
+                    jump(libc::BPF_JEQ, syscall as u32, 0, 3),
+                    load(SYSCALL_ARG0),
+                    jump(libc::BPF_JSET, bits, 1, 0),
+                    ret(action),
+                ]),
+                Rule::Fail(syscall, errno) => filter.extend([
+                    load(SYSCALL_NUMBER),
+                    jump(libc::BPF_JEQ, syscall as u32, 0, 1),
+                    ret(SeccompAction::Errno(errno).value()),
+                ]),
+            }
+        }
+        filter.push(ret(libc::SECCOMP_RET_ALLOW));
+        Ok(Some(filter))
+    }
+
+    /// Compiles the seccomp filter, which is not supported on this architecture.
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn filter(&self) -> io::Result<Option<Vec<sock_filter>>> {
+        if self.presets.is_empty() && self.denied_syscalls.is_empty() {
+            Ok(None)
+        } else {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+    }
+
+    /// Prepares the Landlock ruleset of the allowed paths, for the rights the kernel knows.
+    fn landlock(&self) -> io::Result<Landlock> {
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<u64>(),
+                0,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        if abi < 1 {
+            return Err(io::ErrorKind::Unsupported.into());
+        }
+
+        let mut handled = ACCESS_FS_ABI_1;
+        if abi >= 2 {
+            handled |= ACCESS_FS_REFER;
+        }
+        if abi >= 3 {
+            handled |= ACCESS_FS_TRUNCATE;
+        }
+        if abi >= 5 {
+            handled |= ACCESS_FS_IOCTL_DEV;
+        }
+
+        let paths = self
+            .paths
+            .iter()
+            .map(|(path, access)| {
+                let path = CString::new(path.as_os_str().as_bytes())
+                    .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+                let allowed = match access {
+                    PathAccess::ReadOnly => {
+                        ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR
+                    }
+                    PathAccess::ReadWrite => handled,
+                };
+                Ok((path, allowed))
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Landlock { handled, paths })
+    }
+}
+
+/// A Landlock ruleset ready to be enforced between the fork and the exec.
+#[derive(Debug)]
+struct Landlock {
+    /// The rights denied outside the allowed paths.
+    handled: u64,
+    /// The allowed paths along with their rights.
+    paths: Vec<(CString, u64)>,
+}
+
+/// `struct landlock_ruleset_attr` of the first ABI.
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+/// `struct landlock_path_beneath_attr`.
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: c_int,
+}
+
+impl Landlock {
+    /// Restricts the current process, and the processes it starts, to the allowed paths.
+    ///
+    /// The file descriptors are left open on error, the process exits right away.
+    fn restrict_self(&self) -> io::Result<()> {
+        let attr = RulesetAttr {
+            handled_access_fs: self.handled,
+        };
+        let ruleset = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr,
+                mem::size_of::<RulesetAttr>(),
+                0,
+            )
+        };
+        let ruleset = cvt(ruleset as c_int)?;
+
+        for (path, allowed) in &self.paths {
+            let fd = cvt(unsafe { libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) })?;
+            let mut stat: libc::stat = unsafe { mem::zeroed() };
+            cvt(unsafe { libc::fstat(fd, &mut stat) })?;
+            // Only the rights on files apply to a file
+            let mut allowed = allowed & self.handled;
+            if stat.st_mode & libc::S_IFMT != libc::S_IFDIR {
+                allowed &= ACCESS_FS_FILE;
+            }
+
+            let rule = PathBeneathAttr {
+                allowed_access: allowed,
+                parent_fd: fd,
+            };
+            let added = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset,
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule,
+                    0,
+                )
+            };
+            cvt(added as c_int)?;
+            unsafe { libc::close(fd) };
+        }
+
+        let restricted = unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0) };
+        cvt(restricted as c_int)?;
+        unsafe { libc::close(ruleset) };
+        Ok(())
+    }
+}
+
+/// Returns a BPF instruction loading the 32 bits at `offset` of `seccomp_data`.
+fn load(offset: u32) -> sock_filter {
+    sock_filter {
+        code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+        jt: 0,
+        jf: 0,
+        k: offset,
+    }
+}
+
+/// Returns a BPF instruction comparing the loaded value with `value`, skipping `jt` instructions if the comparison
+/// holds, `jf` otherwise.
+fn jump(comparison: u32, value: u32, jt: u8, jf: u8) -> sock_filter {
+    sock_filter {
+        code: (libc::BPF_JMP | comparison | libc::BPF_K) as u16,
+        jt,
+        jf,
+        k: value,
+    }
+}
+
+/// Returns a BPF instruction returning `value` from the filter.
+fn ret(value: u32) -> sock_filter {
+    sock_filter {
+        code: (libc::BPF_RET | libc::BPF_K) as u16,
+        jt: 0,
+        jf: 0,
+        k: value,
+    }
+}
@@ -0,0 +1,212 @@
+use std::{fs, io, mem, os::unix::process::CommandExt, process::Command};
+
+use libc::{c_int, cpu_set_t, pid_t};
+
+use crate::platform::sys::{cvt, write_file};
+
+/// `IOPRIO_WHO_PROCESS` of `ioprio_set` and `ioprio_get`.
+const IOPRIO_WHO_PROCESS: c_int = 1;
+
+/// Shift of the class in an I/O priority.
+const IOPRIO_CLASS_SHIFT: c_int = 13;
+
+/// Scheduling settings applied to a process before it runs its command, see `SpawnOptions::scheduling`.
+///
+/// The settings are inherited by the descendants of the process. Lowering the niceness or the OOM score adjustment,
+/// and the real-time I/O class, require privileges, spawning fails without them.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use proc_guard::{IoPriority, ProcGuard, ProcessTermination, Scheduling, SpawnOptions};
+///
+/// let guard = ProcGuard::spawn_with(
+///     Command::new("sleep").arg("1"),
+///     ProcessTermination::KillWait,
+///     SpawnOptions::new().scheduling(
+///         Scheduling::new()
+///             .nice(10)
+///             .cpu_affinity([0])
+///             .oom_score_adj(500)
+///             .io_priority(IoPriority::Idle),
+///     ),
+/// )?;
+/// assert_eq!(guard.scheduling()?.nice, 10);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scheduling {
+    nice: Option<i32>,
+    cpu_affinity: Option<Vec<usize>>,
+    oom_score_adj: Option<i32>,
+    io_priority: Option<IoPriority>,
+}
+
+/// The I/O scheduling class of a process, with its level from 0, the highest priority, to 7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoPriority {
+    /// The process is always served first, which requires privileges.
+    RealTime(u8),
+    /// The process is served in turn with the other processes, the default class.
+    BestEffort(u8),
+    /// The process is only served when no other process needs the disk.
+    Idle,
+}
+
+impl IoPriority {
+    /// Returns the value of `ioprio_set`.
+    fn value(&self) -> c_int {
+        let (class, level) = match *self {
+            IoPriority::RealTime(level) => (1, level),
+            IoPriority::BestEffort(level) => (2, level),
+            IoPriority::Idle => (3, 0),
+        };
+        class << IOPRIO_CLASS_SHIFT | c_int::from(level)
+    }
+
+    /// Decodes the value of `ioprio_get`, `None` if no class was set.
+    fn from_value(value: c_int) -> Option<Self> {
+        let level = (value & ((1 << IOPRIO_CLASS_SHIFT) - 1)) as u8;
+        match value >> IOPRIO_CLASS_SHIFT {
+            1 => Some(IoPriority::RealTime(level)),
+            2 => Some(IoPriority::BestEffort(level)),
+            3 => Some(IoPriority::Idle),
+            _ => None,
+        }
+    }
+}
+
+/// The scheduling settings of a running process, see `ProcGuard::scheduling`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchedulingInfo {
+    /// The niceness, from -20, the highest priority, to 19.
+    pub nice: i32,
+    /// The CPUs the process may run on.
+    pub cpu_affinity: Vec<usize>,
+    /// The adjustment of the score the OOM killer uses to pick a victim, from -1000, never killed, to 1000.
+    pub oom_score_adj: i32,
+    /// The I/O priority, `None` if it derives from the niceness.
+    pub io_priority: Option<IoPriority>,
+}
+
+impl Scheduling {
+    /// Creates settings where the process inherits the scheduling of the current process.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the niceness of the process, from -20, the highest priority, to 19.
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    /// Restricts the process to the given CPUs, numbered from 0 and below 1024.
+    pub fn cpu_affinity(mut self, cpus: impl IntoIterator<Item = usize>) -> Self {
+        self.cpu_affinity = Some(cpus.into_iter().collect());
+        self
+    }
+
+    /// Sets the adjustment of the OOM score of the process, from -1000 to 1000, a higher score making the OOM killer
+    /// pick the process first under memory pressure.
+    pub fn oom_score_adj(mut self, adjustment: i32) -> Self {
+        self.oom_score_adj = Some(adjustment);
+        self
+    }
+
+    /// Sets the I/O priority of the process.
+    pub fn io_priority(mut self, priority: IoPriority) -> Self {
+        self.io_priority = Some(priority);
+        self
+    }
+
+    /// Applies the settings in the process spawned by `command`, before it runs the command.
+    ///
+    /// # Errors
+    ///
+    /// * `io::ErrorKind::InvalidInput` - If a CPU of the affinity is not below 1024.
+    pub(crate) fn configure(&self, command: &mut Command) -> io::Result<()> {
+        // Nothing can be allocated between the fork and the exec
+        let nice = self.nice;
+        let cpu_affinity = match &self.cpu_affinity {
+            Some(cpus) => {
+                let mut set: cpu_set_t = unsafe { mem::zeroed() };
+                for &cpu in cpus {
+                    if cpu >= libc::CPU_SETSIZE as usize {
+                        return Err(io::ErrorKind::InvalidInput.into());
+                    }
+                    unsafe { libc::CPU_SET(cpu, &mut set) };
+                }
+                Some(set)
+            }
+            None => None,
+        };
+        let oom_score_adj = self
+            .oom_score_adj
+            .map(|adjustment| adjustment.to_string().into_bytes());
+        let io_priority = self.io_priority.map(|priority| priority.value());
+
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(nice) = nice {
+                    cvt(libc::setpriority(libc::PRIO_PROCESS, 0, nice))?;
+                }
+                if let Some(set) = &cpu_affinity {
+                    cvt(libc::sched_setaffinity(0, mem::size_of::<cpu_set_t>(), set))?;
+                }
+                if let Some(adjustment) = &oom_score_adj {
+                    write_file(b"/proc/self/oom_score_adj\0", adjustment)?;
+                }
+                if let Some(priority) = io_priority {
+                    let set = libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority);
+                    cvt(set as c_int)?;
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+}
+
+impl SchedulingInfo {
+    /// Reads the scheduling settings of a running process.
+    pub(crate) fn read(pid: u32) -> io::Result<Self> {
+        // -1 is a valid niceness, errors are told apart by errno
+        unsafe { *libc::__errno_location() = 0 };
+        let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+        if nice == -1 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(0) {
+                return Err(error);
+            }
+        }
+
+        let mut set: cpu_set_t = unsafe { mem::zeroed() };
+        cvt(unsafe {
+            libc::sched_getaffinity(pid as pid_t, mem::size_of::<cpu_set_t>(), &mut set)
+        })?;
+        let cpu_affinity = (0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .collect();
+
+        let oom_score_adj = fs::read_to_string(format!("/proc/{}/oom_score_adj", pid))?
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+
+        let io_priority = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid) };
+        let io_priority = IoPriority::from_value(cvt(io_priority as c_int)?);
+
+        Ok(SchedulingInfo {
+            nice,
+            cpu_affinity,
+            oom_score_adj,
+            io_priority,
+        })
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    mem, ptr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use libc::{c_int, sigaction};
+
+/// Number of signals whose receptions are counted, signal numbers start at 1.
+const SIGNALS: usize = 65;
+
+/// How many times each signal was received while a handler was installed, never reset so that every
+/// `ShutdownSignals` compares it to the count at its own installation.
+static RECEIVED: [AtomicUsize; SIGNALS] = [const { AtomicUsize::new(0) }; SIGNALS];
+
+/// The signals whose handler is installed, with the number of `ShutdownSignals` using it and the handler it replaced.
+static INSTALLED: Mutex<Vec<Installed>> = Mutex::new(Vec::new());
+
+/// A signal whose handler is installed.
+struct Installed {
+    signal: c_int,
+    users: usize,
+    previous: sigaction,
+}
+
+extern "C" fn on_signal(signal: c_int) {
+    if let Some(received) = RECEIVED.get(signal as usize) {
+        received.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Handlers recording that the current process was asked to stop, so that its guarded processes are shut down.
+///
+/// Several instances can be installed at once, each one telling whether one of its own signals was received since it
+/// was installed. The previous handlers are restored once the last instance handling a signal is dropped.
+#[derive(Debug)]
+pub(crate) struct ShutdownSignals {
+    /// The signals handled, with their reception count when the handlers were installed.
+    signals: Vec<(c_int, usize)>,
+}
+
+impl ShutdownSignals {
+    /// Installs the handlers of `signals`, a signal that cannot be handled being skipped.
+    pub(crate) fn install(signals: &[c_int]) -> Self {
+        let mut installed = INSTALLED.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut handled = Vec::with_capacity(signals.len());
+        for &signal in signals {
+            let received = match RECEIVED.get(signal as usize) {
+                Some(received) if signal > 0 => received,
+                _ => continue,
+            };
+            if handled.iter().any(|&(handled, _)| handled == signal) {
+                continue;
+            }
+
+            match installed
+                .iter_mut()
+                .find(|installed| installed.signal == signal)
+            {
+                Some(installed) => installed.users += 1,
+                None => match install_handler(signal) {
+                    Some(previous) => installed.push(Installed {
+                        signal,
+                        users: 1,
+                        previous,
+                    }),
+                    None => continue,
+                },
+            }
+            handled.push((signal, received.load(Ordering::SeqCst)));
+        }
+        ShutdownSignals { signals: handled }
+    }
+
+    /// Returns whether one of the signals was received since the handlers were installed.
+    pub(crate) fn received(&self) -> bool {
+        self.signals
+            .iter()
+            .any(|&(signal, since)| RECEIVED[signal as usize].load(Ordering::SeqCst) != since)
+    }
+}
+
+/// Installs `on_signal` as the handler of `signal`.
+///
+/// # Returns
+///
+/// * `Some(sigaction)` - The handler replaced.
+/// * `None` - If the signal cannot be handled.
+fn install_handler(signal: c_int) -> Option<sigaction> {
+    unsafe {
+        let mut action: sigaction = mem::zeroed();
+        action.sa_sigaction = on_signal as extern "C" fn(c_int) as usize;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        let mut previous: sigaction = mem::zeroed();
+        if libc::sigaction(signal, &action, &mut previous) == 0 {
+            Some(previous)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for ShutdownSignals {
+    fn drop(&mut self) {
+        let mut installed = INSTALLED.lock().unwrap_or_else(PoisonError::into_inner);
+        for &(signal, _) in self.signals.iter().rev() {
+            let index = match installed
+                .iter()
+                .position(|installed| installed.signal == signal)
+            {
+                Some(index) => index,
+                None => continue,
+            };
+            installed[index].users -= 1;
+            if installed[index].users == 0 {
+                let Installed { previous, .. } = installed.remove(index);
+                unsafe { libc::sigaction(signal, &previous, ptr::null_mut()) };
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::procfs::{self, numeric_entries};
+
+/// Options of the snapshot of `/proc` taken right before a guard kills its process, see `ProcGuard::with_kill_snapshot`.
+///
+/// The snapshot covers the process and its descendants, it is returned in the termination outcome.
+#[derive(Debug, Clone, Default)]
+pub struct KillSnapshot {
+    directory: Option<PathBuf>,
+}
+
+impl KillSnapshot {
+    /// Creates options returning the snapshot in the termination outcome only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also writes the snapshot as a text report in `directory`, which must exist.
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Takes the snapshot of a process about to be killed, writing it if a directory is set.
+    pub(crate) fn take(&self, pid: u32) -> ProcSnapshot {
+        let mut snapshot = ProcSnapshot::capture(pid);
+        if let Some(directory) = &self.directory {
+            // A snapshot that cannot be written is still returned, the kill must go on
+            snapshot.path = snapshot.write_to(directory).ok();
+        }
+        snapshot
+    }
+}
+
+/// The state of a process tree read from `/proc`, to diagnose why a process hung.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcSnapshot {
+    /// When the snapshot was taken.
+    pub taken_at: SystemTime,
+    /// The process and its descendants that could be read, the process first.
+    pub processes: Vec<ProcessSnapshot>,
+    /// The report written by `KillSnapshot::directory`, `None` if it was not written.
+    pub path: Option<PathBuf>,
+}
+
+/// The state of a process read from `/proc/<pid>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessSnapshot {
+    /// The process id.
+    pub pid: u32,
+    /// The command line, one element per argument.
+    pub cmdline: Vec<String>,
+    /// The content of `/proc/<pid>/status`.
+    pub status: String,
+    /// The kernel function the process is blocked in, `None` if it is running.
+    pub wchan: Option<String>,
+    /// The kernel stack of the process, `None` if it is not readable, which requires `CAP_SYS_ADMIN`.
+    pub stack: Option<String>,
+    /// The open file descriptors.
+    pub fds: Vec<OpenFd>,
+    /// The threads of the process.
+    pub threads: Vec<ThreadSnapshot>,
+}
+
+/// An open file descriptor read from `/proc/<pid>/fd`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenFd {
+    /// The file descriptor.
+    pub fd: u32,
+    /// What the file descriptor refers to, such as a path, `pipe:[1234]` or `socket:[5678]`.
+    pub target: String,
+}
+
+/// The state of a thread read from `/proc/<pid>/task/<tid>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadSnapshot {
+    /// The thread id.
+    pub tid: u32,
+    /// The thread name.
+    pub name: String,
+    /// The scheduling state, such as `S (sleeping)` or `D (disk sleep)`.
+    pub state: String,
+    /// The kernel function the thread is blocked in, `None` if it is running.
+    pub wchan: Option<String>,
+}
+
+impl ProcSnapshot {
+    /// Takes a snapshot of a process and its descendants.
+    ///
+    /// Processes that exit while the snapshot is taken are left out, the snapshot has no process if `pid` does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let snapshot = proc_guard::ProcSnapshot::capture(std::process::id());
+    /// assert_eq!(snapshot.processes[0].pid, std::process::id());
+    /// ```
+    pub fn capture(pid: u32) -> Self {
+        let taken_at = SystemTime::now();
+        let processes = std::iter::once(pid)
+            .chain(
+                procfs::descendants(pid)
+                    .into_iter()
+                    .map(|process| process.pid),
+            )
+            .filter_map(|pid| ProcessSnapshot::capture(pid).ok())
+            .collect();
+
+        ProcSnapshot {
+            taken_at,
+            processes,
+            path: None,
+        }
+    }
+
+    /// Writes the snapshot as a text report named `proc-snapshot-<pid>-<timestamp>.txt` in `directory`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PathBuf)` - The path of the report.
+    /// * `Err(io::Error)` - If the report could not be written.
+    pub fn write_to(&self, directory: &Path) -> io::Result<PathBuf> {
+        let since_epoch = self.taken_at.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let pid = self.processes.first().map_or(0, |process| process.pid);
+        let path = directory.join(format!(
+            "proc-snapshot-{}-{}.{:03}.txt",
+            pid,
+            since_epoch.as_secs(),
+            since_epoch.subsec_millis()
+        ));
+
+        fs::write(&path, self.to_string())?;
+        Ok(path)
+    }
+}
+
+impl fmt::Display for ProcSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, process) in self.processes.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", process)?;
+        }
+        Ok(())
+    }
+}
+
+impl ProcessSnapshot {
+    /// Reads the state of a single process.
+    fn capture(pid: u32) -> io::Result<Self> {
+        let dir = PathBuf::from(format!("/proc/{}", pid));
+        let status = fs::read_to_string(dir.join("status"))?;
+        let cmdline = fs::read(dir.join("cmdline"))
+            .map(|cmdline| {
+                cmdline
+                    .split(|&byte| byte == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ProcessSnapshot {
+            pid,
+            cmdline,
+            status,
+            wchan: read_wchan(&dir),
+            stack: fs::read_to_string(dir.join("stack")).ok(),
+            fds: read_fds(&dir),
+            threads: read_threads(&dir),
+        })
+    }
+}
+
+impl fmt::Display for ProcessSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== process {}: {}", self.pid, self.cmdline.join(" "))?;
+        writeln!(f, "wchan: {}", self.wchan.as_deref().unwrap_or("-"))?;
+        writeln!(f, "--- status")?;
+        write!(f, "{}", self.status)?;
+        writeln!(f, "--- stack")?;
+        match &self.stack {
+            Some(stack) => write!(f, "{}", stack)?,
+            None => writeln!(f, "(not readable)")?,
+        }
+        writeln!(f, "--- fds")?;
+        for fd in &self.fds {
+            writeln!(f, "{} -> {}", fd.fd, fd.target)?;
+        }
+        writeln!(f, "--- threads")?;
+        for thread in &self.threads {
+            writeln!(
+                f,
+                "{} {}: {}, wchan: {}",
+                thread.tid,
+                thread.name,
+                thread.state,
+                thread.wchan.as_deref().unwrap_or("-")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads `wchan`, which is `0` when the process or thread is running.
+fn read_wchan(dir: &Path) -> Option<String> {
+    fs::read_to_string(dir.join("wchan"))
+        .ok()
+        .map(|wchan| wchan.trim().to_string())
+        .filter(|wchan| !wchan.is_empty() && wchan != "0")
+}
+
+/// Reads the open file descriptors, sorted by number.
+fn read_fds(dir: &Path) -> Vec<OpenFd> {
+    let mut fds: Vec<OpenFd> = numeric_entries(&dir.join("fd"))
+        .into_iter()
+        .filter_map(|fd| {
+            let target = fs::read_link(dir.join("fd").join(fd.to_string())).ok()?;
+            Some(OpenFd {
+                fd,
+                target: target.to_string_lossy().into_owned(),
+            })
+        })
+        .collect();
+    fds.sort_by_key(|fd| fd.fd);
+    fds
+}
+
+/// Reads the threads, sorted by id.
+fn read_threads(dir: &Path) -> Vec<ThreadSnapshot> {
+    let mut threads: Vec<ThreadSnapshot> = numeric_entries(&dir.join("task"))
+        .into_iter()
+        .filter_map(|tid| {
+            let task = dir.join("task").join(tid.to_string());
+            let status = fs::read_to_string(task.join("status")).ok()?;
+            Some(ThreadSnapshot {
+                tid,
+                name: status_line(&status, "Name").unwrap_or_default(),
+                state: status_line(&status, "State").unwrap_or_default(),
+                wchan: read_wchan(&task),
+            })
+        })
+        .collect();
+    threads.sort_by_key(|thread| thread.tid);
+    threads
+}
+
+/// Returns the value of a `Key:\tvalue` line of a `status` file.
+fn status_line(status: &str, key: &str) -> Option<String> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(|value| value.trim().to_string())
+}
@@ -0,0 +1,282 @@
+use std::{
+    io,
+    process::{Child, Command},
+};
+
+#[cfg(unix)]
+use std::{fs::File, process::Stdio};
+
+#[cfg(unix)]
+use crate::credentials::Credentials;
+#[cfg(target_os = "linux")]
+use crate::namespace;
+#[cfg(target_os = "linux")]
+use crate::network::NetworkNamespace;
+#[cfg(unix)]
+use crate::pty::Pty;
+#[cfg(unix)]
+use crate::rlimit::Rlimits;
+#[cfg(target_os = "linux")]
+use crate::sandbox::Sandbox;
+#[cfg(target_os = "linux")]
+use crate::scheduling::Scheduling;
+#[cfg(target_os = "linux")]
+use crate::subreaper::{Subreaper, SubreaperHandle, Tracking};
+
+/// Options applied when spawning a guarded process with `ProcGuard::spawn_with`.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    #[cfg(unix)]
+    pty: bool,
+    #[cfg(target_os = "linux")]
+    subreaper: Option<SubreaperHandle>,
+    #[cfg(target_os = "linux")]
+    pid_namespace: bool,
+    #[cfg(target_os = "linux")]
+    network_namespace: Option<NetworkNamespace>,
+    #[cfg(unix)]
+    rlimits: Option<Rlimits>,
+    #[cfg(target_os = "linux")]
+    scheduling: Option<Scheduling>,
+    #[cfg(unix)]
+    credentials: Option<Credentials>,
+    #[cfg(target_os = "linux")]
+    sandbox: Option<Sandbox>,
+}
+
+impl SpawnOptions {
+    /// Creates the options of a process spawned like `Command::spawn` does.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the process in a new pseudo-terminal, as the leader of a new session whose controlling terminal is the pseudo-terminal.
+    ///
+    /// The stdin, stdout and stderr of the process are the pseudo-terminal, whatever the `Stdio` configured on the command.
+    /// Ctrl+C steps of the termination strategy write the Ctrl+C character to the pseudo-terminal, so that the whole foreground
+    /// process group receives `SIGINT` as it would from a real terminal.
+    /// The master side is exposed by `ProcGuard::pty_master`, and must be read for the process not to block on a full terminal.
+    #[cfg(unix)]
+    pub fn pty(mut self, pty: bool) -> Self {
+        self.pty = pty;
+        self
+    }
+
+    /// Links the orphaned descendants of the process to its guard, see `Subreaper`.
+    ///
+    /// The process is tagged with an environment variable inherited by its descendants, so that a daemon
+    /// double-forking right away is linked to the guard too.
+    #[cfg(target_os = "linux")]
+    pub fn subreaper(mut self, subreaper: &Subreaper) -> Self {
+        self.subreaper = Some(subreaper.handle());
+        self
+    }
+
+    /// Runs the process in new user and PID namespaces, under a tiny init, so that killing the guarded process kills
+    /// every process it started, whatever their session, process group or parent.
+    ///
+    /// The guarded process relays every signal it receives, such as the Ctrl+C of `send_ctrl_c`, to the init of the
+    /// namespace, which relays it to the command, PID 2 of the namespace. The init reaps the orphans of the namespace,
+    /// and the guarded process exits with the code of the command, 128 plus the signal if it was killed by a signal.
+    /// A kill step kills the guarded process, the init with it and then every process of the namespace.
+    ///
+    /// The user namespace maps the current user and group to themselves, so that no privilege is needed.
+    /// Spawning fails if the system does not allow unprivileged user namespaces.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::Command;
+    /// use proc_guard::{ProcGuard, ProcessTermination, SpawnOptions};
+    ///
+    /// let mut command = Command::new("sh");
+    /// command.args(["-c", "setsid sleep 30 & exec sleep 30"]);
+    /// let guard = ProcGuard::spawn_with(
+    ///     &mut command,
+    ///     ProcessTermination::KillWait,
+    ///     SpawnOptions::new().pid_namespace(true),
+    /// )?;
+    /// // Both sleeps are killed
+    /// guard.terminate()?;
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn pid_namespace(mut self, pid_namespace: bool) -> Self {
+        self.pid_namespace = pid_namespace;
+        self
+    }
+
+    /// Runs the process in a network namespace with only the loopback interface, so that it neither reaches the
+    /// network nor clashes on ports with the host. See `NetworkNamespace`.
+    ///
+    /// The processes spawned with the same namespace share it and reach each other through the loopback interface.
+    /// With `pid_namespace`, the PID namespace is created inside the network namespace. Spawning fails with
+    /// `io::ErrorKind::InvalidInput` along with `credentials`, since the namespace maps no other identity.
+    #[cfg(target_os = "linux")]
+    pub fn network_namespace(mut self, namespace: &NetworkNamespace) -> Self {
+        self.network_namespace = Some(namespace.clone());
+        self
+    }
+
+    /// Applies kernel resource limits to the process before it runs the command, so that a runaway process cannot
+    /// exhaust the resources of the host.
+    ///
+    /// Spawning fails if a limit is above the hard limit of the current process and the current process is not
+    /// privileged. In a PID namespace, the processes relaying the signals are not limited.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    /// use proc_guard::{ProcGuard, ProcessTermination, Rlimit, Rlimits, SpawnOptions};
+    ///
+    /// let mut command = Command::new("sh");
+    /// command.args(["-c", "while :; do :; done"]);
+    /// let guard = ProcGuard::spawn_with(
+    ///     &mut command,
+    ///     ProcessTermination::WaitTimeoutKill(Duration::from_secs(10)),
+    ///     SpawnOptions::new().rlimits(Rlimits::new().cpu_time(Duration::from_secs(1)).core_size(0)),
+    /// )?;
+    /// let exit = guard.supervise()?.exit.expect("No exit info");
+    /// assert_eq!(exit.exceeded_rlimit(), Some(Rlimit::CpuTime));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn rlimits(mut self, rlimits: Rlimits) -> Self {
+        self.rlimits = Some(rlimits);
+        self
+    }
+
+    /// Sets the niceness, CPU affinity, OOM score adjustment and I/O priority of the process before it runs the
+    /// command, so that a background process does not starve the others. See `Scheduling`.
+    ///
+    /// The settings of the running process are read by `ProcGuard::scheduling`.
+    #[cfg(target_os = "linux")]
+    pub fn scheduling(mut self, scheduling: Scheduling) -> Self {
+        self.scheduling = Some(scheduling);
+        self
+    }
+
+    /// Runs the command as another user, group and supplementary groups, without any capability, so that a guard
+    /// running as root can spawn an unprivileged process. See `Credentials`.
+    ///
+    /// The identity is switched after the other settings, which may need privileges, so that the guard still
+    /// signals and kills the process as root. A guard that is not privileged can only spawn the process as itself, with
+    /// its own supplementary groups, spawning fails with `io::ErrorKind::PermissionDenied` otherwise, and its Ctrl+C
+    /// and kill steps fail with `Error::PermissionDenied` once the process runs as another user. Such a guard leaves
+    /// the capability bounding set of the process untouched, clearing it requires `CAP_SETPCAP`.
+    ///
+    /// Spawning fails with `io::ErrorKind::InvalidInput` along with `pid_namespace` or `network_namespace`, whose user
+    /// namespaces map no other identity.
+    #[cfg(unix)]
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Sandboxes the process before it runs the command: it cannot gain privileges anymore, a seccomp filter denies
+    /// syscalls, and Landlock restricts its filesystem access. See `Sandbox`.
+    ///
+    /// The sandbox is applied last, after the other settings and the identity switch of `credentials`, and in a PID
+    /// namespace only to the command, so that the init keeps forwarding signals and reaping orphans.
+    #[cfg(target_os = "linux")]
+    pub fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+}
+
+/// A process spawned with `SpawnOptions`, along with the resources the guard must keep.
+#[derive(Debug)]
+pub(crate) struct Spawned {
+    pub(crate) child: Child,
+    #[cfg(unix)]
+    pub(crate) pty_master: Option<File>,
+    #[cfg(target_os = "linux")]
+    pub(crate) subreaper: Option<Tracking>,
+}
+
+/// Spawns `command` with `options`.
+pub(crate) fn spawn(command: &mut Command, options: &SpawnOptions) -> io::Result<Spawned> {
+    #[cfg(target_os = "linux")]
+    if (options.pid_namespace || options.network_namespace.is_some())
+        && options.credentials.is_some()
+    {
+        return Err(io::ErrorKind::InvalidInput.into());
+    }
+    // The PID namespace is nested in the user namespace of the network namespace
+    #[cfg(target_os = "linux")]
+    if let Some(network_namespace) = &options.network_namespace {
+        network_namespace.configure(command);
+    }
+    // The pseudo-terminal is set up in the command, not in the processes relaying its signals
+    #[cfg(target_os = "linux")]
+    if options.pid_namespace {
+        namespace::configure(command);
+    }
+    #[cfg(unix)]
+    let pty = if options.pty {
+        let pty = Pty::open()?;
+        pty.configure(command)?;
+        Some(pty)
+    } else {
+        None
+    };
+    #[cfg(not(unix))]
+    let _ = options;
+    #[cfg(unix)]
+    if let Some(rlimits) = options.rlimits {
+        rlimits.configure(command);
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(scheduling) = &options.scheduling {
+        scheduling.configure(command)?;
+    }
+    // Last, the other settings may need the privileges the process drops
+    #[cfg(unix)]
+    if let Some(credentials) = &options.credentials {
+        credentials.configure(command)?;
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(sandbox) = &options.sandbox {
+        sandbox.configure(command)?;
+    }
+
+    #[cfg(target_os = "linux")]
+    let tag = options
+        .subreaper
+        .as_ref()
+        .map(|subreaper| subreaper.tag(command));
+
+    let child = command.spawn();
+
+    // The command keeps its stdio, the parent must not hold the slave side once the process is spawned
+    #[cfg(unix)]
+    if pty.is_some() {
+        command
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+    }
+    let child = child?;
+
+    Ok(Spawned {
+        #[cfg(target_os = "linux")]
+        subreaper: options
+            .subreaper
+            .as_ref()
+            .map(|subreaper| subreaper.register(child.id(), tag)),
+        child,
+        #[cfg(unix)]
+        pty_master: pty.map(Pty::into_master),
+    })
+}
@@ -0,0 +1,370 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    os::unix::process::ExitStatusExt,
+    process::{Command, ExitStatus},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use libc::{c_int, pid_t};
+
+use crate::{
+    error::Error,
+    procfs::{self, ProcStat},
+};
+
+/// Interval at which the descendants of the guarded processes are recorded and their orphans reaped.
+const SUBREAPER_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Maximum time to wait for killed orphans to be reaped.
+const ORPHAN_GRACE: Duration = Duration::from_secs(1);
+
+/// The environment variable tagging the processes spawned with `SpawnOptions::subreaper`, inherited by their descendants.
+const TAG_VAR: &str = "PROC_GUARD_TAG";
+
+/// The number of the next tag, unique within the current process.
+static NEXT_TAG: AtomicU64 = AtomicU64::new(0);
+
+/// Marks the current process as a child subreaper, so that the orphaned descendants of guarded processes, such as
+/// daemons that double-fork, are reparented to it instead of to init.
+///
+/// A background thread records the descendants of the processes of the registered guards, so that an orphan is
+/// linked to the guard that spawned its ancestor, and reaps the orphans that exit. The signals of the Ctrl+C and kill
+/// steps of a termination plan are also sent to the orphans of the guard, and the reaped orphans are reported in
+/// the termination outcome.
+///
+/// A daemon double-forks within microseconds, before its ancestry can be recorded. A process spawned with
+/// `SpawnOptions::subreaper` is tagged with an environment variable inherited by its descendants, which links such
+/// orphans to its guard unless they clear their environment. A guard registered with `ProcGuard::with_subreaper` relies
+/// on the recorded ancestry only.
+/// Dropping the subreaper restores the previous subreaper state of the current process.
+///
+/// # Example
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::process::Command;
+/// use std::time::Duration;
+/// use proc_guard::{ProcGuard, ProcessTermination, SpawnOptions, Subreaper};
+///
+/// let subreaper = Subreaper::enable()?;
+/// let mut command = Command::new("sh");
+/// command.args(["-c", "(sleep 30 &); exec sleep 30"]);
+/// let guard = ProcGuard::spawn_with(
+///     &mut command,
+///     ProcessTermination::KillWait,
+///     SpawnOptions::new().subreaper(&subreaper),
+/// )?;
+///
+/// std::thread::sleep(Duration::from_millis(500));
+/// let outcome = guard.terminate_with_output()?;
+/// assert_eq!(outcome.orphans.len(), 1);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Subreaper {
+    handle: SubreaperHandle,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    was_subreaper: bool,
+}
+
+/// An orphaned descendant of a guarded process, reaped by the current process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReapedOrphan {
+    /// The pid of the orphan.
+    pub pid: u32,
+    /// The exit status of the orphan.
+    pub status: ExitStatus,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    next_id: u64,
+    guards: Vec<Tracked>,
+}
+
+/// The descendants recorded for the process of a guard.
+#[derive(Debug)]
+struct Tracked {
+    id: u64,
+    main: u32,
+    /// The tag of the process, if it was spawned with `SpawnOptions::subreaper`.
+    tag: Option<String>,
+    /// The start time of every descendant recorded, by pid.
+    descendants: HashMap<u32, u64>,
+    reaped: Vec<ReapedOrphan>,
+}
+
+impl Subreaper {
+    /// Marks the current process as a child subreaper and starts recording the descendants of the guarded processes.
+    ///
+    /// # Errors
+    ///
+    /// * `proc_guard::Error::Io` - If the current process cannot become a subreaper.
+    pub fn enable() -> Result<Self, Error> {
+        let mut was_subreaper: c_int = 0;
+        if unsafe { libc::prctl(libc::PR_GET_CHILD_SUBREAPER, &mut was_subreaper) } != 0
+            || unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1) } != 0
+        {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let registry = Arc::new(Mutex::new(Registry::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let registry = registry.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    lock(&registry).sample(&procfs::processes());
+                    thread::sleep(SUBREAPER_INTERVAL);
+                }
+            })
+        };
+
+        Ok(Subreaper {
+            handle: SubreaperHandle(registry),
+            stop,
+            thread: Some(thread),
+            was_subreaper: was_subreaper != 0,
+        })
+    }
+
+    /// Starts recording the descendants of a guarded process, until the returned tracking is dropped.
+    pub(crate) fn register(&self, pid: u32) -> Tracking {
+        self.handle.register(pid, None)
+    }
+
+    /// Returns a handle registering the processes spawned with `SpawnOptions::subreaper`.
+    pub(crate) fn handle(&self) -> SubreaperHandle {
+        self.handle.clone()
+    }
+}
+
+/// A handle to the registry of a `Subreaper`.
+#[derive(Debug, Clone)]
+pub(crate) struct SubreaperHandle(Arc<Mutex<Registry>>);
+
+impl SubreaperHandle {
+    /// Tags `command` with a new tag, so that the orphans of the process are linked to its guard even when orphaned
+    /// right away.
+    pub(crate) fn tag(&self, command: &mut Command) -> String {
+        let tag = format!(
+            "{}-{}",
+            std::process::id(),
+            NEXT_TAG.fetch_add(1, Ordering::SeqCst)
+        );
+        command.env(TAG_VAR, &tag);
+        tag
+    }
+
+    /// Starts recording the descendants of a guarded process, until the returned tracking is dropped.
+    pub(crate) fn register(&self, pid: u32, tag: Option<String>) -> Tracking {
+        let mut registry = lock(&self.0);
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry.guards.push(Tracked {
+            id,
+            main: pid,
+            tag,
+            descendants: HashMap::new(),
+            reaped: Vec::new(),
+        });
+        registry.sample(&procfs::processes());
+
+        Tracking {
+            id,
+            registry: self.0.clone(),
+        }
+    }
+}
+
+impl Drop for Subreaper {
+    /// Stops recording the descendants and restores the previous subreaper state.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        if !self.was_subreaper {
+            unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 0) };
+        }
+    }
+}
+
+impl Registry {
+    /// Records the new descendants of every guarded process and reaps their orphans that exited.
+    fn sample(&mut self, processes: &[ProcStat]) {
+        self.claim_tagged(processes);
+        for tracked in &mut self.guards {
+            tracked.sample(processes);
+        }
+    }
+
+    /// Links the orphans whose ancestry was not recorded to the guard whose tag they inherited.
+    fn claim_tagged(&mut self, processes: &[ProcStat]) {
+        let current = std::process::id();
+        for process in processes.iter().filter(|process| process.ppid == current) {
+            let known = self.guards.iter().any(|tracked| {
+                tracked.main == process.pid
+                    || tracked.descendants.get(&process.pid) == Some(&process.start_time)
+            });
+            if known {
+                continue;
+            }
+
+            if let Some(tag) = read_tag(process.pid) {
+                if let Some(tracked) = self
+                    .guards
+                    .iter_mut()
+                    .find(|tracked| tracked.tag.as_ref() == Some(&tag))
+                {
+                    tracked.descendants.insert(process.pid, process.start_time);
+                }
+            }
+        }
+    }
+
+    fn tracked(&mut self, id: u64) -> Option<&mut Tracked> {
+        self.guards.iter_mut().find(|tracked| tracked.id == id)
+    }
+}
+
+impl Tracked {
+    fn sample(&mut self, processes: &[ProcStat]) {
+        // Forget the descendants that were reaped, their pids may be reused
+        self.descendants.retain(|pid, start_time| {
+            processes
+                .iter()
+                .any(|process| process.pid == *pid && process.start_time == *start_time)
+        });
+
+        // The orphans keep their own descendants, they are not below the main process anymore
+        let mut roots: Vec<u32> = self.descendants.keys().copied().collect();
+        roots.push(self.main);
+        for root in roots {
+            for process in procfs::tree(processes, root) {
+                self.descendants.insert(process.pid, process.start_time);
+            }
+        }
+
+        let zombies: Vec<u32> = self
+            .orphans(processes)
+            .filter(|orphan| orphan.state == 'Z')
+            .map(|orphan| orphan.pid)
+            .collect();
+        for pid in zombies {
+            // Only the current process reaps its orphans, a zombie orphan keeps its pid until then
+            let mut status = 0;
+            if unsafe { libc::waitpid(pid as pid_t, &mut status, libc::WNOHANG) } == pid as pid_t {
+                self.descendants.remove(&pid);
+                self.reaped.push(ReapedOrphan {
+                    pid,
+                    status: ExitStatus::from_raw(status),
+                });
+            }
+        }
+    }
+
+    /// Returns the recorded descendants that were reparented to the current process.
+    fn orphans<'a>(&'a self, processes: &'a [ProcStat]) -> impl Iterator<Item = &'a ProcStat> {
+        let current = std::process::id();
+        processes.iter().filter(move |process| {
+            process.ppid == current
+                && process.pid != self.main
+                && self.descendants.get(&process.pid) == Some(&process.start_time)
+        })
+    }
+
+    /// Returns the orphans and their descendants that are still running, orphans first.
+    fn orphan_trees(&self, processes: &[ProcStat]) -> Vec<ProcStat> {
+        let mut trees = Vec::new();
+        for orphan in self.orphans(processes) {
+            trees.push(*orphan);
+            trees.extend(procfs::tree(processes, orphan.pid));
+        }
+        trees.retain(|process| process.state != 'Z');
+        trees
+    }
+}
+
+/// The recording of the descendants of a guarded process by a `Subreaper`, stopped on drop.
+#[derive(Debug)]
+pub(crate) struct Tracking {
+    id: u64,
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl Tracking {
+    /// Sends `signal` to the orphans of the guarded process and to their descendants.
+    pub(crate) fn signal_orphans(&self, signal: c_int) {
+        let mut registry = lock(&self.registry);
+        let processes = procfs::processes();
+        registry.sample(&processes);
+        let tracked = match registry.tracked(self.id) {
+            Some(tracked) => tracked,
+            None => return,
+        };
+
+        for process in tracked.orphan_trees(&processes) {
+            // A process that is not an orphan may have exited since, its pid must still identify it
+            let _ = process.signal(signal);
+        }
+    }
+
+    /// Returns the orphans reaped so far, waiting for the remaining orphans to exit if `wait` is true.
+    pub(crate) fn collect(&self, wait: bool) -> Vec<ReapedOrphan> {
+        let deadline = Instant::now() + ORPHAN_GRACE;
+        loop {
+            {
+                let mut registry = lock(&self.registry);
+                let processes = procfs::processes();
+                registry.sample(&processes);
+                let tracked = match registry.tracked(self.id) {
+                    Some(tracked) => tracked,
+                    None => return Vec::new(),
+                };
+
+                if !wait
+                    || Instant::now() >= deadline
+                    || tracked.orphan_trees(&processes).is_empty()
+                {
+                    return tracked.reaped.clone();
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for Tracking {
+    fn drop(&mut self) {
+        lock(&self.registry)
+            .guards
+            .retain(|tracked| tracked.id != self.id);
+    }
+}
+
+fn lock(registry: &Mutex<Registry>) -> MutexGuard<'_, Registry> {
+    registry.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Reads the tag a process inherited from `/proc/<pid>/environ`.
+fn read_tag(pid: u32) -> Option<String> {
+    let environ = fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    environ.split(|&byte| byte == 0).find_map(|variable| {
+        let value = variable
+            .strip_prefix(TAG_VAR.as_bytes())?
+            .strip_prefix(b"=")?;
+        String::from_utf8(value.to_vec()).ok()
+    })
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use libc::c_int;
+
+use crate::procfs::{self, ProcStat};
+
+/// The order in which a tree kill signals the processes of a tree, see `ProcGuard::with_tree_kill`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeOrder {
+    /// Parents are signaled before their children, the guarded process first.
+    /// A parent cannot react to its children exiting, such as by respawning them.
+    TopDown,
+    /// Children are signaled before their parents, the guarded process last.
+    /// A parent can still see its children exit, such as a shell waiting for its pipeline.
+    BottomUp,
+}
+
+/// The descendants of a guarded process recorded from `/proc` during a termination.
+///
+/// Every descendant seen is kept, so that a process reparented to init once its parent exited after a step still
+/// receives the following steps.
+#[derive(Debug)]
+pub(crate) struct TreeKill {
+    order: TreeOrder,
+    /// The start time of every descendant seen, by pid.
+    descendants: HashMap<u32, u64>,
+}
+
+impl TreeKill {
+    /// Starts recording the descendants of a process terminated in `order`.
+    pub(crate) fn new(order: TreeOrder) -> Self {
+        TreeKill {
+            order,
+            descendants: HashMap::new(),
+        }
+    }
+
+    /// Sends `signal` to the descendants of `pid`, and calls `signal_main` to signal the process itself, in the order
+    /// of the tree kill.
+    ///
+    /// A descendant that exited is skipped, the signal never reaches another process reusing its pid.
+    pub(crate) fn signal<E>(
+        &mut self,
+        pid: u32,
+        signal: c_int,
+        signal_main: impl FnOnce() -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut descendants = self.sample(pid);
+        if self.order == TreeOrder::BottomUp {
+            descendants.reverse();
+            signal_descendants(&descendants, signal);
+            return signal_main();
+        }
+
+        signal_main()?;
+        signal_descendants(&descendants, signal);
+        Ok(())
+    }
+
+    /// Records the descendants of `pid` and returns the living ones, parents before their children.
+    fn sample(&mut self, pid: u32) -> Vec<ProcStat> {
+        let processes = procfs::processes();
+        // The known descendants reparented to init once their parent exited are the roots of their own trees,
+        // their pid must still identify them
+        let reparented = processes
+            .iter()
+            .filter(|process| self.descendants.get(&process.pid) == Some(&process.start_time));
+
+        let mut descendants = procfs::tree(&processes, pid);
+        for root in reparented {
+            for process in std::iter::once(*root).chain(procfs::tree(&processes, root.pid)) {
+                if !descendants.contains(&process) {
+                    descendants.push(process);
+                }
+            }
+        }
+
+        self.descendants = descendants
+            .iter()
+            .map(|process| (process.pid, process.start_time))
+            .collect();
+        descendants
+    }
+}
+
+/// Sends `signal` to every process of `processes` in order, the processes that exited are skipped.
+fn signal_descendants(processes: &[ProcStat], signal: c_int) {
+    for process in processes {
+        // A descendant that cannot be signaled must not spare the rest of the tree
+        let _ = process.signal(signal);
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    io, mem,
+    os::unix::process::ExitStatusExt,
+    process::{Child, ExitStatus},
+    thread,
+    time::{Duration, Instant},
+};
+
+use libc::pid_t;
+
+/// Interval at which a process is checked while waiting for it with a timeout, when it cannot be polled.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The resources used by a process and its reaped descendants, collected with `wait4` when its guard reaped it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceUsage {
+    /// The CPU time spent in user mode.
+    pub user_time: Duration,
+    /// The CPU time spent in kernel mode.
+    pub system_time: Duration,
+    /// The peak resident memory, in bytes.
+    pub max_rss: u64,
+    /// The page faults served without I/O.
+    pub minor_faults: u64,
+    /// The page faults that required I/O.
+    pub major_faults: u64,
+    /// The context switches because the process waited for a resource.
+    pub voluntary_context_switches: u64,
+    /// The context switches because the process was preempted.
+    pub involuntary_context_switches: u64,
+}
+
+impl ResourceUsage {
+    /// Returns the total CPU time, in user and kernel mode.
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
+
+    /// Converts the usage reported by the system.
+    fn from_rusage(usage: &libc::rusage) -> Self {
+        let duration = |time: libc::timeval| {
+            Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+        };
+        // Linux reports kilobytes, macOS bytes
+        let rss_unit = if cfg!(target_os = "macos") { 1 } else { 1024 };
+
+        ResourceUsage {
+            user_time: duration(usage.ru_utime),
+            system_time: duration(usage.ru_stime),
+            max_rss: usage.ru_maxrss as u64 * rss_unit,
+            minor_faults: usage.ru_minflt as u64,
+            major_faults: usage.ru_majflt as u64,
+            voluntary_context_switches: usage.ru_nvcsw as u64,
+            involuntary_context_switches: usage.ru_nivcsw as u64,
+        }
+    }
+}
+
+/// A process reaped by `wait`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Reaped {
+    pub(crate) status: ExitStatus,
+    /// The resources used by the process, `None` if it was already reaped through the `Child`.
+    pub(crate) usage: Option<ResourceUsage>,
+}
+
+/// Waits for a process to exit and reaps it with `wait4`, collecting its resource usage.
+///
+/// The `Child` is not told about the exit, it must not be waited for nor killed anymore.
+///
+/// # Returns
+///
+/// * `Ok(Some(Reaped))` - If the process exited.
+/// * `Ok(None)` - If `timeout` elapsed first.
+/// * `Err(io::Error)` - If waiting for the process failed.
+pub(crate) fn wait(child: &mut Child, timeout: Option<Duration>) -> io::Result<Option<Reaped>> {
+    if !wait_exited(child, timeout)? {
+        return Ok(None);
+    }
+    reap(child)
+}
+
+/// Waits for a process to exit without reaping it, without timeout if `timeout` is `None`.
+///
+/// # Returns
+///
+/// * `Ok(true)` - If the process exited, its pid is not reused until it is reaped.
+/// * `Ok(false)` - If `timeout` elapsed first.
+/// * `Err(io::Error)` - If waiting for the process failed.
+pub(crate) fn wait_exited(child: &mut Child, timeout: Option<Duration>) -> io::Result<bool> {
+    match timeout {
+        None => peek(child, true),
+        Some(timeout) => wait_exit(child, Instant::now() + timeout),
+    }
+}
+
+/// Reaps a process that exited with `wait4`, collecting its resource usage.
+///
+/// # Returns
+///
+/// * `Ok(Some(Reaped))` - If the process exited.
+/// * `Ok(None)` - If the process is still running.
+/// * `Err(io::Error)` - If waiting for the process failed.
+pub(crate) fn reap(child: &mut Child) -> io::Result<Option<Reaped>> {
+    let pid = child.id() as pid_t;
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    if unsafe { libc::wait4(pid, &mut status, libc::WNOHANG, &mut usage) } == pid {
+        return Ok(Some(Reaped {
+            status: ExitStatus::from_raw(status),
+            usage: Some(ResourceUsage::from_rusage(&usage)),
+        }));
+    }
+
+    // The `Child` reaped the process, it keeps its status
+    Ok(child.try_wait()?.map(|status| Reaped {
+        status,
+        usage: None,
+    }))
+}
+
+/// Returns whether the process exited, without reaping it.
+pub(crate) fn exited(child: &mut Child) -> io::Result<bool> {
+    peek(child, false)
+}
+
+/// Waits until the process exited or `deadline` passed, without reaping it.
+fn wait_exit(child: &mut Child, deadline: Instant) -> io::Result<bool> {
+    #[cfg(target_os = "linux")]
+    if let Some(exited) = poll_pidfd(child, deadline)? {
+        return Ok(exited);
+    }
+
+    loop {
+        if peek(child, false)? {
+            return Ok(true);
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(false);
+        }
+        thread::sleep(remaining.min(WAIT_POLL_INTERVAL));
+    }
+}
+
+/// Waits until the process exited or `deadline` passed by polling a pidfd, `None` without pidfds, before Linux 5.3.
+#[cfg(target_os = "linux")]
+fn poll_pidfd(child: &mut Child, deadline: Instant) -> io::Result<Option<bool>> {
+    let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, child.id() as pid_t, 0) };
+    if pidfd < 0 {
+        let error = io::Error::last_os_error();
+        return match error.raw_os_error() {
+            Some(libc::ENOSYS) => Ok(None),
+            // The `Child` reaped the process
+            Some(libc::ESRCH) => Ok(Some(true)),
+            _ => Err(error),
+        };
+    }
+
+    let mut poll = libc::pollfd {
+        fd: pidfd as libc::c_int,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let result = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        // Rounded up, the deadline must have passed once the poll times out
+        let timeout = remaining
+            .as_micros()
+            .div_ceil(1000)
+            .min(libc::c_int::MAX as u128) as libc::c_int;
+        match unsafe { libc::poll(&mut poll, 1, timeout) } {
+            -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => continue,
+            -1 => break Err(io::Error::last_os_error()),
+            0 if Instant::now() < deadline => continue,
+            ready => break Ok(Some(ready > 0)),
+        }
+    };
+    unsafe { libc::close(pidfd as libc::c_int) };
+    result
+}
+
+/// Returns whether the process exited without reaping it, waiting for it to exit if `block` is true.
+fn peek(child: &mut Child, block: bool) -> io::Result<bool> {
+    let mut flags = libc::WEXITED | libc::WNOWAIT;
+    if !block {
+        flags |= libc::WNOHANG;
+    }
+
+    loop {
+        let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+        if unsafe { libc::waitid(libc::P_PID, child.id() as libc::id_t, &mut info, flags) } == 0 {
+            // `si_pid` stays 0 when nothing exited with `WNOHANG`
+            return Ok(unsafe { info.si_pid() } != 0);
+        }
+
+        let error = io::Error::last_os_error();
+        match error.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            // The `Child` reaped the process
+            Some(libc::ECHILD) => return Ok(child.try_wait()?.is_some()),
+            _ => return Err(error),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+#[cfg(unix)]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{Credentials, ProcGuard, ProcessTermination, SpawnOptions};
+    use std::fs;
+    use std::io::Read;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    /// The uid and gid of `nobody`.
+    const NOBODY: u32 = 65534;
+
+    /// Set when the test binary is run as `nobody` by `test_unprivileged_guard`.
+    const UNPRIVILEGED: &str = "PROC_GUARD_UNPRIVILEGED_TEST";
+
+    /// Spawns `script` as `nobody`, `None` if the current process is not privileged enough to switch users.
+    fn spawn_as_nobody(
+        script: &str,
+        termination: ProcessTermination,
+        groups: &[u32],
+    ) -> Option<ProcGuard> {
+        if unsafe { libc::geteuid() } != 0 {
+            eprintln!("Skipped, switching users requires root");
+            return None;
+        }
+        let mut command = utilities::shell_command(script);
+        command.stdout(Stdio::piped());
+        let credentials = Credentials::new(NOBODY, NOBODY).groups(groups.iter().copied());
+        let guard = ProcGuard::spawn_with(
+            &mut command,
+            termination,
+            SpawnOptions::new().credentials(credentials),
+        )
+        .expect("Failed to start process");
+        Some(guard)
+    }
+
+    /// Returns the value of the field `name` in the status of the current process, as printed by `script`.
+    fn field<'a>(status: &'a str, name: &str) -> &'a str {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .unwrap_or_else(|| panic!("No {} in {}", name, status))
+            .trim()
+    }
+
+    #[test]
+    fn test_credentials_applied() {
+        let mut guard = match spawn_as_nobody(
+            "cat /proc/self/status",
+            ProcessTermination::Wait,
+            &[NOBODY, 100],
+        ) {
+            Some(guard) => guard,
+            None => return,
+        };
+        let mut status = String::new();
+        guard
+            .mut_child()
+            .stdout
+            .take()
+            .expect("No stdout")
+            .read_to_string(&mut status)
+            .expect("Failed to read the output");
+        assert!(guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+
+        let ids = "65534\t65534\t65534\t65534";
+        assert_eq!(field(&status, "Uid"), ids);
+        assert_eq!(field(&status, "Gid"), ids);
+        assert_eq!(field(&status, "Groups"), "100 65534");
+        #[cfg(target_os = "linux")]
+        for capabilities in ["CapInh", "CapPrm", "CapEff", "CapBnd", "CapAmb"] {
+            assert_eq!(field(&status, capabilities), "0000000000000000");
+        }
+    }
+
+    #[test]
+    fn test_ctrl_c_across_users() {
+        let script = "trap 'exit 7' INT; while :; do sleep 0.1; done";
+        let guard = match spawn_as_nobody(script, ProcessTermination::CtrlCWait, &[]) {
+            Some(guard) => guard,
+            None => return,
+        };
+        thread::sleep(Duration::from_millis(300));
+
+        let status = guard.terminate().expect("Termination failed");
+        assert_eq!(status.expect("No exit status").code(), Some(7));
+    }
+
+    #[test]
+    fn test_kill_across_users() {
+        let guard = match spawn_as_nobody("exec sleep 30", ProcessTermination::KillWait, &[]) {
+            Some(guard) => guard,
+            None => return,
+        };
+
+        let status = guard.terminate().expect("Termination failed");
+        assert_eq!(
+            status.expect("No exit status").signal(),
+            Some(libc::SIGKILL)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pid_namespace_conflict() {
+        let error = ProcGuard::spawn_with(
+            &mut utilities::sleep_command("30"),
+            ProcessTermination::KillWait,
+            SpawnOptions::new()
+                .pid_namespace(true)
+                .credentials(Credentials::new(NOBODY, NOBODY)),
+        )
+        .expect_err("The namespace maps no other user");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_unprivileged_guard() {
+        if unsafe { libc::geteuid() } != 0 {
+            // The current process is already unprivileged
+            return unprivileged_guard_as_itself();
+        }
+
+        // The test binary is run again as nobody, which cannot access the build directory
+        let directory =
+            std::env::temp_dir().join(format!("proc_guard_unprivileged_{}", std::process::id()));
+        fs::create_dir_all(&directory).expect("Failed to create the directory");
+        fs::set_permissions(&directory, fs::Permissions::from_mode(0o755))
+            .expect("Failed to set the permissions");
+        let binary = directory.join("credentials_test");
+        fs::copy(std::env::current_exe().expect("No test binary"), &binary)
+            .expect("Failed to copy the test binary");
+
+        let mut command = Command::new(&binary);
+        command
+            .args([
+                "--exact",
+                "tests::test_unprivileged_guard_inner",
+                "--nocapture",
+            ])
+            .env(UNPRIVILEGED, "1")
+            .current_dir(&directory);
+        let status = ProcGuard::spawn_with(
+            &mut command,
+            ProcessTermination::Wait,
+            SpawnOptions::new().credentials(Credentials::new(NOBODY, NOBODY)),
+        )
+        .expect("Failed to start process")
+        .terminate();
+        let _ = fs::remove_dir_all(&directory);
+
+        assert!(status
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+    }
+
+    /// Run as nobody by `test_unprivileged_guard`.
+    #[test]
+    fn test_unprivileged_guard_inner() {
+        if std::env::var_os(UNPRIVILEGED).is_some() {
+            unprivileged_guard_as_itself();
+        }
+    }
+
+    /// Spawns a process as the current unprivileged user, with its supplementary groups.
+    fn unprivileged_guard_as_itself() {
+        assert_ne!(unsafe { libc::geteuid() }, 0);
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+        let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+        let mut groups = vec![0; count as usize];
+        let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+        groups.truncate(count as usize);
+
+        let guard = ProcGuard::spawn_with(
+            &mut utilities::shell_command("exit 0"),
+            ProcessTermination::Wait,
+            SpawnOptions::new().credentials(Credentials::new(uid, gid).groups(groups)),
+        )
+        .expect("An unprivileged guard spawns the process as itself");
+        assert!(guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(unix)]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{terminate, DiagnosticDump, OutputStream, ProcGuard, TerminationPlan};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Stdio;
+    use std::time::{Duration, Instant};
+
+    /// A process that ignores Ctrl+C and prints a dump on SIGQUIT, like a hung service would.
+    fn spawn_dumping(script: &str, plan: TerminationPlan) -> ProcGuard {
+        let script = format!(
+            "trap '' INT; {}; echo ready; while true; do sleep 0.1; done",
+            script
+        );
+        let mut command = utilities::shell_command(&script);
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let mut guard = ProcGuard::spawn(&mut command, plan).expect("Failed to start process");
+        guard
+            .wait_for_line("ready", Duration::from_secs(5))
+            .expect("Process not ready");
+        guard
+    }
+
+    #[test]
+    fn test_dump_stderr_then_kill() {
+        let plan = TerminationPlan::new()
+            .ctrl_c()
+            .wait_timeout(Duration::from_millis(300))
+            .dump(DiagnosticDump::new().window(Duration::from_secs(1)))
+            .kill()
+            .wait();
+        let guard = spawn_dumping("trap 'echo goroutine 1 [running] >&2' QUIT", plan);
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert_eq!(
+            outcome.status.expect("No exit status").signal(),
+            Some(libc::SIGKILL)
+        );
+        assert_eq!(outcome.diagnostics.len(), 1);
+        let diagnostics = &outcome.diagnostics[0];
+        assert_eq!(diagnostics.signal, libc::SIGQUIT);
+        assert_eq!(
+            diagnostics.output.as_deref(),
+            Some(&b"goroutine 1 [running]\n"[..])
+        );
+        assert!(diagnostics.file.is_none());
+    }
+
+    #[test]
+    fn test_dump_stdout_custom_signal() {
+        let plan = TerminationPlan::new()
+            .dump(
+                DiagnosticDump::new()
+                    .signal(libc::SIGUSR1)
+                    .stream(OutputStream::Stdout)
+                    .window(Duration::from_secs(1)),
+            )
+            .kill()
+            .wait();
+        let guard = spawn_dumping("trap 'echo Full thread dump' USR1", plan);
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        let diagnostics = &outcome.diagnostics[0];
+        assert_eq!(diagnostics.signal, libc::SIGUSR1);
+        assert_eq!(
+            diagnostics.output.as_deref(),
+            Some(&b"Full thread dump\n"[..])
+        );
+    }
+
+    #[test]
+    fn test_dump_process_exits() {
+        let plan = TerminationPlan::new()
+            .dump(DiagnosticDump::new().window(Duration::from_secs(5)))
+            .kill()
+            .wait();
+        let guard = spawn_dumping("trap 'echo SIGQUIT: quit >&2; exit 2' QUIT", plan);
+
+        let start = Instant::now();
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert!(start.elapsed() < Duration::from_secs(4));
+        assert_eq!(outcome.status.expect("No exit status").code(), Some(2));
+        assert_eq!(
+            outcome.diagnostics[0].output.as_deref(),
+            Some(&b"SIGQUIT: quit\n"[..])
+        );
+    }
+
+    #[test]
+    fn test_dump_file() {
+        let path = std::env::temp_dir().join(format!("proc_guard_dump_{}", std::process::id()));
+        std::fs::write(&path, "previous dump\n").expect("Failed to write dump file");
+
+        let plan = TerminationPlan::new()
+            .dump(
+                DiagnosticDump::new()
+                    .file(&path)
+                    .window(Duration::from_secs(1)),
+            )
+            .kill()
+            .wait();
+        let script = format!("trap 'echo heap dump >> {}' QUIT", path.display());
+        let guard = spawn_dumping(&script, plan);
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        std::fs::remove_file(&path).expect("Failed to remove dump file");
+        assert_eq!(
+            outcome.diagnostics[0].file.as_deref(),
+            Some(&b"heap dump\n"[..])
+        );
+    }
+
+    #[test]
+    fn test_dump_without_guard() {
+        let mut child = utilities::sleep_child("5");
+        let plan = TerminationPlan::new()
+            .dump(DiagnosticDump::new().window(Duration::from_millis(500)))
+            .kill()
+            .wait();
+
+        // sleep dies from SIGQUIT, the plan ends with its exit status
+        let status = terminate(&mut child, plan)
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGQUIT));
+    }
+
+    #[test]
+    fn test_no_dump() {
+        let guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            TerminationPlan::new().kill().wait(),
+        );
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert!(outcome.diagnostics.is_empty());
+    }
+}
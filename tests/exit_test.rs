@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use proc_guard::{EscalationStep, ExitInfo, ExitKind, ProcGuard, TerminationPlan};
+    use std::time::Duration;
+
+    #[test]
+    fn test_exit_code() {
+        let status = utilities::shell_command("exit 3")
+            .status()
+            .expect("Failed to run process");
+        let info = ExitInfo::from(status);
+        assert_eq!(info.kind, ExitKind::Exited(3));
+        assert!(!info.success());
+        assert!(!info.ended_by_guard());
+        assert_eq!(info.to_string(), "exited with code 3");
+    }
+
+    #[test]
+    fn test_outcome_exited() {
+        let guard = ProcGuard::spawn(
+            &mut utilities::shell_command("exit 0"),
+            TerminationPlan::new().kill().wait(),
+        )
+        .expect("Failed to start process");
+        let outcome = guard.supervise().expect("Supervision failed");
+        let exit = outcome.exit.expect("No exit info");
+        assert!(exit.success());
+        assert!(exit.escalation.is_none());
+    }
+
+    #[test]
+    fn test_outcome_without_status() {
+        let guard = ProcGuard::new(utilities::sleep_child("5"), TerminationPlan::new().kill());
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert!(outcome.exit.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_killed_by_escalation() {
+        let plan = TerminationPlan::new()
+            .wait_timeout(Duration::from_secs(1))
+            .kill()
+            .wait();
+        let guard = ProcGuard::new(utilities::sleep_child("5"), plan);
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+
+        let exit = outcome.exit.expect("No exit info");
+        assert_eq!(
+            exit.kind,
+            ExitKind::Signaled {
+                signal: libc::SIGKILL,
+                core_dumped: false
+            }
+        );
+        let escalation = exit.escalation.expect("Not ended by the guard");
+        assert_eq!(escalation.step, EscalationStep::Kill);
+        assert!(escalation.after >= Duration::from_secs(1));
+        assert_eq!(exit.to_string(), "terminated by SIGKILL after 1s grace");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_interrupted_by_escalation() {
+        let guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            TerminationPlan::new().ctrl_c().wait(),
+        );
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+
+        let exit = outcome.exit.expect("No exit info");
+        assert_eq!(
+            exit.kind,
+            ExitKind::Signaled {
+                signal: libc::SIGINT,
+                core_dumped: false
+            }
+        );
+        assert_eq!(
+            exit.escalation.expect("Not ended by the guard").step,
+            EscalationStep::CtrlC
+        );
+        // The grace period depends on how fast the process exits
+        assert!(exit.to_string().starts_with("terminated by SIGINT after "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_killed_externally() {
+        let guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            TerminationPlan::new().kill().wait(),
+        );
+        unsafe { libc::kill(guard.child().id() as libc::pid_t, libc::SIGTERM) };
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        let exit = outcome.exit.expect("No exit info");
+        assert!(!exit.ended_by_guard());
+        assert_eq!(exit.to_string(), "killed by SIGTERM");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_not_sent_by_guard() {
+        // The process is killed by someone else before the plan sends SIGINT
+        let guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            TerminationPlan::new().ctrl_c().wait(),
+        );
+        unsafe { libc::kill(guard.child().id() as libc::pid_t, libc::SIGTERM) };
+        std::thread::sleep(Duration::from_millis(200));
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        let exit = outcome.exit.expect("No exit info");
+        assert!(exit.escalation.is_none());
+        assert_eq!(exit.to_string(), "killed by SIGTERM");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_raw_statuses() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+
+        let core_dumped = ExitInfo::from(ExitStatus::from_raw(libc::SIGSEGV | 0x80));
+        assert_eq!(
+            core_dumped.kind,
+            ExitKind::Signaled {
+                signal: libc::SIGSEGV,
+                core_dumped: true
+            }
+        );
+        assert_eq!(core_dumped.to_string(), "killed by SIGSEGV (core dumped)");
+
+        let stopped = ExitInfo::from(ExitStatus::from_raw((libc::SIGSTOP << 8) | 0x7f));
+        assert_eq!(stopped.kind, ExitKind::Stopped(libc::SIGSTOP));
+        assert_eq!(stopped.to_string(), "stopped by SIGSTOP");
+
+        let continued = ExitInfo::from(ExitStatus::from_raw(0xffff));
+        assert_eq!(continued.kind, ExitKind::Continued);
+        assert_eq!(continued.to_string(), "continued");
+
+        let unnamed = ExitInfo::from(ExitStatus::from_raw(40));
+        assert_eq!(unnamed.to_string(), "killed by signal 40");
+    }
+}
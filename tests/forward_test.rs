@@ -0,0 +1,121 @@
+#[cfg(unix)]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{Error, ProcGuard, ProcessTermination, SignalForwarder};
+    use std::io;
+    use std::os::unix::process::ExitStatusExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Only one forwarder can be installed at a time, the tests take turns.
+    static INSTALLED: Mutex<()> = Mutex::new(());
+
+    fn serialize() -> MutexGuard<'static, ()> {
+        INSTALLED
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn raise(signal: i32) {
+        assert_eq!(unsafe { libc::raise(signal) }, 0);
+    }
+
+    #[test]
+    fn test_forward_to_every_guard() {
+        let _installed = serialize();
+        let forwarder = SignalForwarder::install(&[libc::SIGUSR1]).expect("Install failed");
+        let mut guards = [
+            ProcGuard::new(utilities::sleep_child("5"), ProcessTermination::KillWait)
+                .with_signal_forwarder(&forwarder),
+            ProcGuard::new(utilities::sleep_child("5"), ProcessTermination::KillWait)
+                .with_signal_forwarder(&forwarder),
+        ];
+
+        raise(libc::SIGUSR1);
+
+        // The signal is relayed asynchronously, terminating a guard would unregister it
+        let deadline = Instant::now() + Duration::from_secs(3);
+        for guard in &mut guards {
+            let status = loop {
+                match guard.mut_child().try_wait().expect("Wait failed") {
+                    Some(status) => break status,
+                    None if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                    None => panic!("The signal was not forwarded"),
+                }
+            };
+            assert_eq!(status.signal(), Some(libc::SIGUSR1));
+        }
+        forwarder.uninstall().expect("Uninstall failed");
+    }
+
+    #[test]
+    fn test_released_guard_not_forwarded() {
+        let _installed = serialize();
+        let forwarder = SignalForwarder::install(&[libc::SIGUSR1]).expect("Install failed");
+        let mut child = ProcGuard::new(utilities::sleep_child("1"), ProcessTermination::Wait)
+            .with_signal_forwarder(&forwarder)
+            .release();
+
+        raise(libc::SIGUSR1);
+        thread::sleep(Duration::from_millis(200));
+
+        let status = child.wait().expect("Wait failed");
+        assert!(status.success());
+        forwarder.uninstall().expect("Uninstall failed");
+    }
+
+    #[test]
+    fn test_single_forwarder() {
+        let _installed = serialize();
+        let forwarder = SignalForwarder::install(&[libc::SIGUSR1]).expect("Install failed");
+
+        match SignalForwarder::install(&[libc::SIGUSR2]) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::AlreadyExists),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        drop(forwarder);
+        SignalForwarder::install(&[libc::SIGUSR2])
+            .expect("Install failed")
+            .uninstall()
+            .expect("Uninstall failed");
+    }
+
+    #[test]
+    fn test_invalid_signal_restores_handlers() {
+        let _installed = serialize();
+        assert!(SignalForwarder::install(&[libc::SIGUSR1, libc::SIGKILL]).is_err());
+
+        // The handler of SIGUSR1 was restored and the forwarder uninstalled
+        SignalForwarder::install(&[libc::SIGUSR1])
+            .expect("Install failed")
+            .uninstall()
+            .expect("Uninstall failed");
+    }
+
+    static HANDLED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn on_sigusr2(_: libc::c_int) {
+        HANDLED.store(true, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_uninstall_restores_previous_handler() {
+        let _installed = serialize();
+        let handler = on_sigusr2 as extern "C" fn(libc::c_int);
+        let previous = unsafe { libc::signal(libc::SIGUSR2, handler as libc::sighandler_t) };
+
+        let forwarder = SignalForwarder::install(&[libc::SIGUSR2]).expect("Install failed");
+        raise(libc::SIGUSR2);
+        thread::sleep(Duration::from_millis(100));
+        assert!(!HANDLED.load(Ordering::SeqCst));
+
+        forwarder.uninstall().expect("Uninstall failed");
+        raise(libc::SIGUSR2);
+        assert!(HANDLED.load(Ordering::SeqCst));
+
+        unsafe { libc::signal(libc::SIGUSR2, previous) };
+    }
+}
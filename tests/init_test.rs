@@ -0,0 +1,105 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{InitMode, ProcessTermination};
+    use std::process::Command;
+    use std::time::Duration;
+
+    /// The script of the main process run by `init_helper`, set when the test binary runs inside the namespaces.
+    const SCRIPT_VAR: &str = "PROC_GUARD_INIT_SCRIPT";
+
+    /// The outcome of an init, as reported by `init_helper`.
+    #[derive(Debug)]
+    struct Report {
+        exit_code: i32,
+        shutdown: bool,
+        reaped: usize,
+    }
+
+    /// Runs `init_helper` as the PID 1 of new unprivileged user and PID namespaces.
+    /// Returns `None` if the namespaces cannot be created on this system.
+    fn run_init(script: &str) -> Option<Report> {
+        let output = Command::new("unshare")
+            .args(["--user", "--map-root-user", "--pid", "--fork"])
+            .arg(std::env::current_exe().expect("No test binary"))
+            .args(["--exact", "tests::init_helper", "--nocapture"])
+            .env(SCRIPT_VAR, script)
+            .output();
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("Skipped, unshare is not available: {}", e);
+                return None;
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // The report follows the name of the test on the same line
+        let report = match stdout.lines().find_map(|line| line.split_once("INIT ")) {
+            Some((_, report)) => report,
+            None => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                assert!(stderr.starts_with("unshare:"), "Init failed: {}", stderr);
+                eprintln!("Skipped, namespaces are not available: {}", stderr);
+                return None;
+            }
+        };
+
+        let fields: Vec<&str> = report.split_whitespace().collect();
+        Some(Report {
+            exit_code: fields[0].parse().expect("Invalid exit code"),
+            shutdown: fields[1].parse().expect("Invalid shutdown"),
+            reaped: fields[2].parse().expect("Invalid reaped count"),
+        })
+    }
+
+    /// Runs the init when the test binary is started by `run_init`, does nothing otherwise.
+    #[test]
+    fn init_helper() {
+        let script = match std::env::var(SCRIPT_VAR) {
+            Ok(script) => script,
+            Err(_) => return,
+        };
+        assert_eq!(std::process::id(), 1);
+
+        let outcome = InitMode::new(ProcessTermination::CtrlCWaitTimeoutKill(
+            Duration::from_secs(2),
+        ))
+        .run(&mut utilities::shell_command(&script))
+        .expect("Init failed");
+        println!(
+            "INIT {} {} {}",
+            outcome.exit_code,
+            outcome.shutdown,
+            outcome.reaped.len()
+        );
+        std::process::exit(0);
+    }
+
+    #[test]
+    fn test_init_reaps_orphans() {
+        if let Some(report) = run_init("(sleep 0.1 &); (sleep 0.1 &); sleep 1; exit 3") {
+            assert_eq!(report.exit_code, 3);
+            assert!(!report.shutdown);
+            assert_eq!(report.reaped, 2);
+        }
+    }
+
+    #[test]
+    fn test_init_shutdown_on_sigterm() {
+        if let Some(report) = run_init("kill -TERM 1; exec sleep 30") {
+            assert!(report.shutdown);
+            assert_eq!(report.exit_code, 128 + libc::SIGINT);
+        }
+    }
+
+    #[test]
+    fn test_init_forwards_signals() {
+        if let Some(report) =
+            run_init("trap 'exit 7' USR1; kill -USR1 1; while :; do sleep 0.1; done")
+        {
+            assert!(!report.shutdown);
+            assert_eq!(report.exit_code, 7);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(target_os = "linux")]
+
+#[cfg(test)]
+mod tests {
+    use proc_guard::{
+        sample_resources, ProcGuard, ProcessTermination, ResourceLimit, ResourceLimits,
+        TerminationReason,
+    };
+    use std::process::Command;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sample_resources() {
+        let child = utilities::sleep_child("3");
+        let guard = ProcGuard::new(child, ProcessTermination::KillWait);
+
+        let sample = sample_resources(guard.child().id()).expect("Sampling failed");
+        assert!(sample.rss > 0);
+        assert!(sample.fds >= 3);
+        assert_eq!(sample.threads, 1);
+    }
+
+    #[test]
+    fn test_sample_resources_no_process() {
+        assert!(sample_resources(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_supervise_exited() {
+        let child = utilities::sleep_child("1");
+        let guard = ProcGuard::new(child, ProcessTermination::KillWait)
+            .with_resource_limits(ResourceLimits::new().max_fds(1024));
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert!(matches!(outcome.reason, TerminationReason::Exited));
+        assert!(outcome.status.expect("No exit status").success());
+    }
+
+    #[test]
+    fn test_supervise_fds_exceeded() {
+        let child = utilities::sleep_child("5");
+        let guard = ProcGuard::new(child, ProcessTermination::KillWait)
+            .with_resource_limits(ResourceLimits::new().max_fds(1));
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        match outcome.reason {
+            TerminationReason::LimitExceeded(exceeded) => {
+                assert_eq!(exceeded.limit, ResourceLimit::Fds(1));
+                assert!(exceeded.sample.fds > 1);
+            }
+            reason => panic!("Unexpected reason: {:?}", reason),
+        }
+        assert!(!outcome.status.expect("No exit status").success());
+    }
+
+    #[test]
+    fn test_supervise_rss_exceeded_kill() {
+        let child = utilities::sleep_child("5");
+        let guard = ProcGuard::new(child, ProcessTermination::Kill)
+            .with_resource_limits(ResourceLimits::new().max_rss(1));
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert!(matches!(
+            outcome.reason,
+            TerminationReason::LimitExceeded(exceeded) if exceeded.limit == ResourceLimit::Rss(1)
+        ));
+        assert!(outcome.status.is_none());
+    }
+
+    #[test]
+    fn test_supervise_cpu_time_exceeded() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "while :; do :; done"]);
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_resource_limits(
+                ResourceLimits::new()
+                    .max_cpu_time(Duration::from_millis(200))
+                    .interval(Duration::from_millis(20)),
+            );
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        match outcome.reason {
+            TerminationReason::LimitExceeded(exceeded) => {
+                assert_eq!(
+                    exceeded.limit,
+                    ResourceLimit::CpuTime(Duration::from_millis(200))
+                );
+                assert!(exceeded.sample.cpu_time > Duration::from_millis(200));
+            }
+            reason => panic!("Unexpected reason: {:?}", reason),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{ProcGuard, ProcessTermination, SpawnOptions};
+    use std::io::Read;
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Spawns `script` in a new PID namespace, `None` if namespaces are not available on this system.
+    fn spawn_in_namespace(script: &str, termination: ProcessTermination) -> Option<ProcGuard> {
+        let mut command = utilities::shell_command(script);
+        command.stdout(Stdio::piped());
+        match ProcGuard::spawn_with(
+            &mut command,
+            termination,
+            SpawnOptions::new().pid_namespace(true),
+        ) {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                eprintln!("Skipped, namespaces are not available: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Returns the pids of the running processes whose command line contains `marker`.
+    fn find_processes(marker: &str) -> Vec<u32> {
+        std::fs::read_dir("/proc")
+            .expect("No /proc")
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .filter(|pid: &u32| {
+                let running = std::fs::read_to_string(format!("/proc/{}/stat", pid))
+                    .is_ok_and(|stat| !stat.contains(") Z "));
+                let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+                running && String::from_utf8_lossy(&cmdline).contains(marker)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_command_runs_as_pid_2() {
+        let mut guard = match spawn_in_namespace("echo $$", ProcessTermination::Wait) {
+            Some(guard) => guard,
+            None => return,
+        };
+        let mut output = String::new();
+        guard
+            .mut_child()
+            .stdout
+            .take()
+            .expect("No stdout")
+            .read_to_string(&mut output)
+            .expect("Failed to read the output");
+
+        let status = guard.terminate().expect("Termination failed");
+        assert!(status.expect("No exit status").success());
+        assert_eq!(output.trim(), "2");
+    }
+
+    #[test]
+    fn test_kill_tears_down_namespace() {
+        // Neither the session nor the parent of the sleep link it to the guarded process
+        let script = "(setsid sleep 30.0417 &); exec sleep 30";
+        let guard = match spawn_in_namespace(script, ProcessTermination::KillWait) {
+            Some(guard) => guard,
+            None => return,
+        };
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(find_processes("30.0417").len(), 1);
+
+        guard.terminate().expect("Termination failed");
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !find_processes("30.0417").is_empty() {
+            assert!(Instant::now() < deadline, "The namespace was not torn down");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_ctrl_c_forwarded_to_command() {
+        let script = "trap 'exit 7' INT; while :; do sleep 0.1; done";
+        let guard = match spawn_in_namespace(script, ProcessTermination::CtrlCWait) {
+            Some(guard) => guard,
+            None => return,
+        };
+        thread::sleep(Duration::from_millis(300));
+
+        let status = guard.terminate().expect("Termination failed");
+        assert_eq!(status.expect("No exit status").code(), Some(7));
+    }
+}
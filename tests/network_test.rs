@@ -0,0 +1,164 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{Credentials, NetworkNamespace, ProcGuard, ProcessTermination, SpawnOptions};
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::process::{Command, Stdio};
+
+    /// Serves one connection on port 5000 of the loopback interface.
+    const SERVER: &str = r#"$s = IO::Socket::INET->new(LocalAddr => "127.0.0.1:5000", Listen => 1) or exit 2; $c = $s->accept; print $c "hello\n""#;
+
+    /// Connects to port 5000 of the loopback interface, retrying while the server starts, and prints what it reads.
+    const CLIENT: &str = r#"for (1..100) { $c = IO::Socket::INET->new("127.0.0.1:5000") and last; select(undef, undef, undef, 0.05) } $c or exit 3; print scalar <$c>"#;
+
+    /// Creates a network namespace, `None` if namespaces are not available on this system.
+    fn new_namespace() -> Option<NetworkNamespace> {
+        match NetworkNamespace::new() {
+            Ok(namespace) => Some(namespace),
+            Err(e) => {
+                eprintln!("Skipped, namespaces are not available: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Spawns a Perl script in `namespace`, `None` if Perl is not available.
+    fn spawn_perl(script: &str, namespace: &NetworkNamespace) -> Option<ProcGuard> {
+        let mut command = Command::new("perl");
+        command
+            .args(["-MIO::Socket::INET", "-e", script])
+            .stdout(Stdio::piped());
+        match ProcGuard::spawn_with(
+            &mut command,
+            ProcessTermination::WaitTimeoutKill(std::time::Duration::from_secs(10)),
+            SpawnOptions::new().network_namespace(namespace),
+        ) {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                eprintln!("Skipped, perl is not available: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Waits for `guard` to exit and returns its exit code along with its output.
+    fn finish(mut guard: ProcGuard) -> (Option<i32>, String) {
+        let mut output = String::new();
+        guard
+            .mut_child()
+            .stdout
+            .take()
+            .expect("No stdout")
+            .read_to_string(&mut output)
+            .expect("Failed to read the output");
+        let status = guard.terminate().expect("Termination failed");
+        (status.and_then(|status| status.code()), output)
+    }
+
+    #[test]
+    fn test_only_loopback() {
+        let namespace = match new_namespace() {
+            Some(namespace) => namespace,
+            None => return,
+        };
+        let mut command = utilities::shell_command("cat /proc/self/net/dev");
+        command.stdout(Stdio::piped());
+        let guard = ProcGuard::spawn_with(
+            &mut command,
+            ProcessTermination::Wait,
+            SpawnOptions::new().network_namespace(&namespace),
+        )
+        .expect("Failed to start process");
+
+        let (code, output) = finish(guard);
+        assert_eq!(code, Some(0));
+        // Two header lines, then one line per interface
+        let interfaces: Vec<_> = output
+            .lines()
+            .skip(2)
+            .filter_map(|line| line.split(':').next())
+            .map(str::trim)
+            .collect();
+        assert_eq!(interfaces, ["lo"]);
+    }
+
+    #[test]
+    fn test_host_unreachable() {
+        let namespace = match new_namespace() {
+            Some(namespace) => namespace,
+            None => return,
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to listen");
+        let port = listener.local_addr().expect("No address").port();
+
+        let script = format!(
+            r#"IO::Socket::INET->new("127.0.0.1:{}") ? exit 0 : exit 3"#,
+            port
+        );
+        let guard = match spawn_perl(&script, &namespace) {
+            Some(guard) => guard,
+            None => return,
+        };
+        assert_eq!(finish(guard).0, Some(3));
+    }
+
+    #[test]
+    fn test_shared_namespace() {
+        let (first, second) = match (new_namespace(), new_namespace()) {
+            (Some(first), Some(second)) => (first, second),
+            _ => return,
+        };
+        // The port is free in each namespace, whatever listens on the host or in the other namespace
+        let mut servers = Vec::new();
+        for namespace in [&first, &second] {
+            match spawn_perl(SERVER, namespace) {
+                Some(server) => servers.push(server),
+                None => return,
+            }
+        }
+
+        for (server, namespace) in servers.into_iter().zip([&first, &second]) {
+            let client = spawn_perl(CLIENT, namespace).expect("Failed to start the client");
+            assert_eq!(finish(client), (Some(0), "hello\n".to_string()));
+            assert_eq!(finish(server).0, Some(0));
+        }
+    }
+
+    #[test]
+    fn test_with_pid_namespace() {
+        let namespace = match new_namespace() {
+            Some(namespace) => namespace,
+            None => return,
+        };
+        let mut command = utilities::shell_command("echo $$; grep -c : /proc/self/net/dev");
+        command.stdout(Stdio::piped());
+        let guard = ProcGuard::spawn_with(
+            &mut command,
+            ProcessTermination::Wait,
+            SpawnOptions::new()
+                .network_namespace(&namespace)
+                .pid_namespace(true),
+        )
+        .expect("Failed to start process");
+
+        assert_eq!(finish(guard), (Some(0), "2\n1\n".to_string()));
+    }
+
+    #[test]
+    fn test_credentials_conflict() {
+        let namespace = match new_namespace() {
+            Some(namespace) => namespace,
+            None => return,
+        };
+        let error = ProcGuard::spawn_with(
+            &mut utilities::sleep_command("30"),
+            ProcessTermination::KillWait,
+            SpawnOptions::new()
+                .network_namespace(&namespace)
+                .credentials(Credentials::new(65534, 65534)),
+        )
+        .expect_err("The namespace maps no other user");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,337 @@
+#[cfg(test)]
+mod tests {
+    use proc_guard::{
+        Error, OutputMode, OutputStream, ProcGuard, ProcessTermination, RecentOutputLimit,
+        TerminationReason,
+    };
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_idle_timeout_silent() {
+        let mut command = utilities::sleep_command("5");
+        command.stdout(Stdio::piped());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_idle_timeout(Duration::from_millis(500));
+
+        let start = Instant::now();
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert!(start.elapsed() < Duration::from_secs(4));
+        assert!(matches!(
+            outcome.reason,
+            TerminationReason::IdleTimeout(timeout) if timeout == Duration::from_millis(500)
+        ));
+        assert!(outcome.status.is_some());
+        assert!(outcome.output.is_none());
+    }
+
+    #[test]
+    fn test_capture_exited() {
+        let mut command = utilities::shell_command("echo hello");
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_output(OutputMode::Capture);
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert!(matches!(outcome.reason, TerminationReason::Exited));
+        let output = outcome.output.expect("No captured output");
+        assert!(output.stdout.starts_with(b"hello"));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_idle_timeout_chatty() {
+        let mut command =
+            utilities::shell_command("for i in 1 2 3 4 5; do echo $i; sleep 0.2; done");
+        command.stdout(Stdio::piped());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_output(OutputMode::Capture)
+            .with_idle_timeout(Duration::from_secs(1));
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert!(matches!(outcome.reason, TerminationReason::Exited));
+        assert!(outcome.status.expect("No exit status").success());
+        assert_eq!(
+            outcome.output.expect("No captured output").stdout,
+            b"1\n2\n3\n4\n5\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_idle_timeout_not_piped() {
+        // The output of the process is not seen by the guard
+        let guard = ProcGuard::spawn(
+            &mut utilities::sleep_command("1"),
+            ProcessTermination::KillWait,
+        )
+        .expect("Failed to start process")
+        .with_idle_timeout(Duration::from_millis(200));
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert!(matches!(outcome.reason, TerminationReason::Exited));
+        assert!(outcome.status.expect("No exit status").success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_idle_timeout_after_output() {
+        let mut command = utilities::shell_command("echo start; echo oops >&2; exec sleep 5");
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_idle_timeout(Duration::from_millis(500))
+            .with_output(OutputMode::Capture);
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert!(matches!(outcome.reason, TerminationReason::IdleTimeout(_)));
+        assert!(!outcome.status.expect("No exit status").success());
+        let output = outcome.output.expect("No captured output");
+        assert_eq!(output.stdout, b"start\n");
+        assert_eq!(output.stderr, b"oops\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_drain_discard_wait() {
+        // More than a pipe can hold, the process would block forever if it was not drained
+        let mut command = utilities::shell_command("head -c 1000000 /dev/zero");
+        command.stdout(Stdio::piped());
+        let guard = ProcGuard::spawn(
+            &mut command,
+            ProcessTermination::WaitTimeout(Duration::from_secs(5)),
+        )
+        .expect("Failed to start process")
+        .with_output(OutputMode::Discard);
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert!(matches!(outcome.reason, TerminationReason::Requested));
+        assert!(outcome.status.expect("No exit status").success());
+        assert!(outcome.output.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_drain_capture_wait() {
+        let mut command =
+            utilities::shell_command("head -c 1000000 /dev/zero; head -c 100000 /dev/zero >&2");
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let guard = ProcGuard::spawn(
+            &mut command,
+            ProcessTermination::WaitTimeoutKill(Duration::from_secs(5)),
+        )
+        .expect("Failed to start process")
+        .with_output(OutputMode::Capture);
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        let output = outcome.output.expect("No captured output");
+        assert_eq!(output.stdout.len(), 1000000);
+        assert_eq!(output.stderr.len(), 100000);
+    }
+
+    #[test]
+    fn test_drain_terminate() {
+        let mut command = utilities::shell_command("echo hello");
+        command.stdout(Stdio::piped());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::Wait)
+            .expect("Failed to start process")
+            .with_output(OutputMode::Discard);
+
+        assert!(guard.terminate().expect("Termination failed").is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recent_output_lines() {
+        let mut command = utilities::shell_command(
+            "for i in 1 2 3 4 5; do echo $i; done; printf partial; exit 3",
+        );
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_output(OutputMode::Discard)
+            .with_recent_output(RecentOutputLimit::Lines(3));
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert_eq!(outcome.status.expect("No exit status").code(), Some(3));
+        let recent = outcome.recent_output.expect("No recent output");
+        let lines: Vec<_> = recent
+            .lines
+            .iter()
+            .map(|line| (line.stream, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (OutputStream::Stdout, "4"),
+                (OutputStream::Stdout, "5"),
+                (OutputStream::Stdout, "partial"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recent_output_bytes() {
+        let mut command = utilities::shell_command("echo aaaa; echo bbbb; echo cccc");
+        command.stdout(Stdio::piped());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::Wait)
+            .expect("Failed to start process")
+            .with_recent_output(RecentOutputLimit::Bytes(9))
+            .with_output(OutputMode::Capture);
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert_eq!(
+            outcome.output.expect("No captured output").stdout,
+            b"aaaa\nbbbb\ncccc\n"
+        );
+        let recent = outcome.recent_output.expect("No recent output");
+        assert_eq!(recent.to_string(), "[stdout] bbbb\n[stdout] cccc");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recent_output_error() {
+        let mut command = utilities::shell_command("echo still running >&2; exec sleep 5");
+        command.stderr(Stdio::piped());
+        let guard = ProcGuard::spawn(
+            &mut command,
+            ProcessTermination::WaitTimeout(Duration::from_secs(1)),
+        )
+        .expect("Failed to start process")
+        .with_recent_output(RecentOutputLimit::Lines(10));
+
+        match guard.terminate() {
+            Err(error @ Error::WithRecentOutput(_, _)) => {
+                assert!(error.to_string().ends_with("[stderr] still running"));
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_for_line() {
+        let mut command = utilities::shell_command(
+            "echo starting; echo listening on 8080; echo a; echo b; sleep 5",
+        );
+        command.stdout(Stdio::piped());
+        let mut guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_unread_lines()
+            .with_output(OutputMode::Discard);
+
+        let line = guard
+            .wait_for_line("listening on", Duration::from_secs(5))
+            .expect("Line not found");
+        assert_eq!(line.stream, OutputStream::Stdout);
+        assert_eq!(line.text, "listening on 8080");
+
+        let line = guard
+            .wait_for_line(|line: &str| line == "a", Duration::from_secs(5))
+            .expect("Line not found");
+        assert_eq!(line.text, "a");
+
+        // The last line may not be read yet
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut unread = guard.take_unread_lines();
+        while unread.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+            unread = guard.take_unread_lines();
+        }
+        assert_eq!(unread.len(), 1);
+        assert_eq!(unread[0].text, "b");
+
+        assert!(guard.terminate().expect("Termination failed").is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_for_line_read_before_call() {
+        let mut command = utilities::shell_command("echo ready; exec sleep 5");
+        command.stdout(Stdio::piped());
+        let mut guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_unread_lines()
+            .with_output(OutputMode::Discard);
+
+        // The line is read by the guard before anyone waits for it
+        thread::sleep(Duration::from_millis(500));
+
+        let line = guard
+            .wait_for_line("ready", Duration::from_millis(500))
+            .expect("Line not found");
+        assert_eq!(line.text, "ready");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_for_line_timeout() {
+        let mut command = utilities::shell_command("echo starting >&2; exec sleep 5");
+        command.stderr(Stdio::piped());
+        let mut guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process");
+
+        let start = Instant::now();
+        match guard.wait_for_line("listening on", Duration::from_millis(500)) {
+            Err(Error::LineNotFound(seen)) => {
+                assert_eq!(seen.lines.len(), 1);
+                assert_eq!(seen.lines[0].stream, OutputStream::Stderr);
+                assert_eq!(seen.lines[0].text, "starting");
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        // The guard is still valid
+        assert!(guard.mut_child().try_wait().expect("Wait failed").is_none());
+        assert!(guard.terminate().expect("Termination failed").is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_for_line_output_ended() {
+        let mut command = utilities::shell_command("printf 'one\\ntwo'");
+        command.stdout(Stdio::piped());
+        let mut guard = ProcGuard::spawn(&mut command, ProcessTermination::Wait)
+            .expect("Failed to start process")
+            .with_unread_lines()
+            .with_output(OutputMode::Discard);
+
+        let start = Instant::now();
+        match guard.wait_for_line(String::from("three"), Duration::from_secs(5)) {
+            Err(Error::LineNotFound(seen)) => {
+                assert_eq!(seen.to_string(), "[stdout] one\n[stdout] two")
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unread_lines_bounded() {
+        // 4 MiB of lines, more than the unread lines kept
+        let mut command = utilities::shell_command(
+            "head -c 4194304 /dev/zero | tr '\\0' x | fold -w 1023; exec sleep 5",
+        );
+        command.stdout(Stdio::piped());
+        let mut guard = ProcGuard::spawn(&mut command, ProcessTermination::KillWait)
+            .expect("Failed to start process")
+            .with_unread_lines()
+            .with_output(OutputMode::Discard);
+
+        thread::sleep(Duration::from_secs(1));
+
+        let unread = guard.take_unread_lines();
+        let bytes: usize = unread.iter().map(|line| line.text.len()).sum();
+        assert!(!unread.is_empty());
+        assert!(bytes <= 1024 * 1024);
+        assert!(guard.terminate().expect("Termination failed").is_some());
+    }
+}
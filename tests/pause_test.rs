@@ -0,0 +1,240 @@
+#[cfg(unix)]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{PauseMethod, ProcGuard, ProcessTermination};
+    use std::os::unix::process::ExitStatusExt;
+    use std::time::Duration;
+
+    /// Returns the state of a process from `/proc/<pid>/stat`.
+    #[cfg(target_os = "linux")]
+    fn state(pid: u32) -> String {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).expect("No such process");
+        stat.rsplit_once(')')
+            .and_then(|(_, fields)| fields.split_whitespace().next())
+            .expect("Malformed stat")
+            .to_string()
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let mut guard = ProcGuard::new(utilities::sleep_child("5"), ProcessTermination::KillWait);
+        assert!(guard.paused().is_none());
+
+        assert_eq!(guard.pause().expect("Pause failed"), PauseMethod::Signal);
+        assert_eq!(guard.paused(), Some(&PauseMethod::Signal));
+        #[cfg(target_os = "linux")]
+        assert_eq!(state(guard.child().id()), "T");
+
+        // Pausing again does nothing
+        assert_eq!(guard.pause().expect("Pause failed"), PauseMethod::Signal);
+
+        guard.resume().expect("Resume failed");
+        assert!(guard.paused().is_none());
+        #[cfg(target_os = "linux")]
+        assert_ne!(state(guard.child().id()), "T");
+
+        // Resuming again does nothing
+        guard.resume().expect("Resume failed");
+    }
+
+    #[test]
+    fn test_terminate_paused() {
+        let mut guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            ProcessTermination::CtrlCWaitTimeout(Duration::from_secs(2)),
+        );
+        guard.pause().expect("Pause failed");
+
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+    }
+
+    #[test]
+    fn test_send_ctrl_c_paused() {
+        let mut guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            ProcessTermination::WaitTimeout(Duration::from_secs(2)),
+        );
+        guard.pause().expect("Pause failed");
+        guard.send_ctrl_c().expect("Ctrl+C failed");
+        assert!(guard.paused().is_none());
+
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+    }
+
+    #[test]
+    fn test_kill_paused() {
+        let mut guard = ProcGuard::new(utilities::sleep_child("5"), ProcessTermination::KillWait);
+        guard.pause().expect("Pause failed");
+
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_terminate_stopped_externally() {
+        let guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            ProcessTermination::CtrlCWaitTimeout(Duration::from_secs(2)),
+        );
+        unsafe { libc::kill(guard.child().id() as libc::pid_t, libc::SIGSTOP) };
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(state(guard.child().id()), "T");
+
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+    }
+
+    /// Creates a cgroup v2 named after `name`, `None` if the current process cannot create one.
+    #[cfg(target_os = "linux")]
+    fn create_cgroup(name: &str) -> Option<std::path::PathBuf> {
+        let mounts = std::fs::read_to_string("/proc/self/mounts").expect("No mounts");
+        let cgroup = mounts
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .find(|fields| fields[2] == "cgroup2")
+            .map(|fields| std::path::PathBuf::from(fields[1]))?
+            .join(format!("proc_guard_{}_{}", name, std::process::id()));
+        // Creating a cgroup requires privileges
+        std::fs::create_dir(&cgroup).ok()?;
+        Some(cgroup)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pause_cgroup_freeze() {
+        let cgroup = match create_cgroup("pause") {
+            Some(cgroup) => cgroup,
+            None => return,
+        };
+
+        let mut guard = ProcGuard::new(
+            utilities::sleep_child("5"),
+            ProcessTermination::CtrlCWaitTimeout(Duration::from_secs(2)),
+        );
+        std::fs::write(cgroup.join("cgroup.procs"), guard.child().id().to_string())
+            .expect("Failed to move process");
+
+        let events = || std::fs::read_to_string(cgroup.join("cgroup.events")).expect("No events");
+        assert_eq!(
+            guard.pause().expect("Pause failed"),
+            PauseMethod::CgroupFreeze(cgroup.clone())
+        );
+        assert!(events().contains("frozen 1"));
+        guard.resume().expect("Resume failed");
+        assert!(events().contains("frozen 0"));
+
+        guard.pause().expect("Pause failed");
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+        assert!(events().contains("frozen 0"));
+        std::fs::remove_dir(&cgroup).expect("Failed to remove cgroup");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_kill_thaws_cgroup() {
+        use std::io::{BufRead, BufReader};
+        use std::process::Stdio;
+
+        let cgroup = match create_cgroup("kill") {
+            Some(cgroup) => cgroup,
+            None => return,
+        };
+
+        // The descendant is started once the shell was moved to the cgroup
+        let mut command = utilities::shell_command("sleep 0.3; sleep 30 & echo $!; exec sleep 30");
+        command.stdout(Stdio::piped()).stderr(Stdio::null());
+        let mut guard = ProcGuard::spawn(&mut command, ProcessTermination::Kill)
+            .expect("Failed to start process");
+        std::fs::write(cgroup.join("cgroup.procs"), guard.child().id().to_string())
+            .expect("Failed to move process");
+        let mut line = String::new();
+        BufReader::new(guard.mut_child().stdout.take().expect("No stdout"))
+            .read_line(&mut line)
+            .expect("Failed to read the descendant");
+        let descendant: libc::pid_t = line.trim().parse().expect("Invalid pid");
+
+        assert!(matches!(
+            guard.pause().expect("Pause failed"),
+            PauseMethod::CgroupFreeze(_)
+        ));
+        // No step follows the kill
+        assert!(guard.terminate().expect("Termination failed").is_none());
+
+        // The descendant is left running, not frozen, the cgroup is cleaned up before checking it
+        let events = std::fs::read_to_string(cgroup.join("cgroup.events")).expect("No events");
+        let _ = std::fs::write(cgroup.join("cgroup.freeze"), "0");
+        unsafe { libc::kill(descendant, libc::SIGKILL) };
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while std::fs::remove_dir(&cgroup).is_err() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "The cgroup is not empty"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(events.contains("frozen 0"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pause_shared_cgroup() {
+        let cgroup = match create_cgroup("shared") {
+            Some(cgroup) => cgroup,
+            None => return,
+        };
+
+        let mut guard = ProcGuard::new(utilities::sleep_child("5"), ProcessTermination::KillWait);
+        let mut unrelated = utilities::sleep_child("5");
+        for pid in [guard.child().id(), unrelated.id()] {
+            std::fs::write(cgroup.join("cgroup.procs"), pid.to_string())
+                .expect("Failed to move process");
+        }
+
+        // The unrelated process must not be frozen with the guarded one
+        assert_eq!(guard.pause().expect("Pause failed"), PauseMethod::Signal);
+        let events = std::fs::read_to_string(cgroup.join("cgroup.events")).expect("No events");
+        guard.resume().expect("Resume failed");
+        assert!(guard.terminate().expect("Termination failed").is_some());
+        unrelated.kill().expect("Failed to kill");
+        unrelated.wait().expect("Wait failed");
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while std::fs::remove_dir(&cgroup).is_err() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "The cgroup is not empty"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(events.contains("frozen 0"));
+    }
+
+    #[test]
+    fn test_pause_reaped() {
+        let mut guard = ProcGuard::new(utilities::sleep_child("0"), ProcessTermination::KillWait);
+        guard.mut_child().wait().expect("Wait failed");
+
+        // The pid may be reused, nothing is signaled
+        assert!(guard.pause().is_err());
+        guard.resume().expect("Resume failed");
+        assert!(guard.terminate().is_ok());
+    }
+}
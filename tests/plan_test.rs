@@ -0,0 +1,206 @@
+#[cfg(test)]
+mod tests {
+    use proc_guard::{
+        terminate, Error, ProcGuard, ProcessTermination, TerminationPlan, TerminationStep,
+    };
+    use std::process::Command;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_send_sync_termination_plan() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<TerminationPlan>();
+    }
+
+    #[test]
+    fn test_plan_kill_wait() {
+        let child = utilities::sleep_child("5");
+        let guard = ProcGuard::new(child, TerminationPlan::new().kill().wait());
+        assert!(guard.terminate().expect("Termination failed").is_some());
+    }
+
+    #[test]
+    fn test_plan_empty() {
+        let mut child = utilities::sleep_child("3");
+        let result = terminate(&mut child, TerminationPlan::new());
+        assert!(result.expect("Termination failed").is_none());
+        assert!(child.try_wait().expect("Wait failed").is_none());
+        child.kill().expect("Kill failed");
+    }
+
+    #[test]
+    fn test_plan_last_wait_timeout() {
+        let mut child = utilities::sleep_child("3");
+        let result = terminate(
+            &mut child,
+            TerminationPlan::new().wait_timeout(Duration::from_millis(500)),
+        );
+        assert!(matches!(result, Err(Error::Timeout)));
+        child.kill().expect("Kill failed");
+    }
+
+    #[test]
+    fn test_strategy_wait_timeout_error() {
+        // The strategies run as plans, their wait times out like the last step of a plan
+        let mut child = utilities::sleep_child("3");
+        let result = terminate(
+            &mut child,
+            ProcessTermination::WaitTimeout(Duration::from_millis(200)),
+        );
+        assert!(matches!(result, Err(Error::Timeout)));
+        child.kill().expect("Kill failed");
+    }
+
+    #[test]
+    fn test_custom_step_closure() {
+        let called = Arc::new(AtomicBool::new(false));
+        let step_called = called.clone();
+        let plan = TerminationPlan::new()
+            .step(move |pid: u32, deadline: Option<Instant>| {
+                assert!(deadline.is_none());
+                assert!(pid > 0);
+                step_called.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+            .kill()
+            .wait();
+
+        let child = utilities::sleep_child("5");
+        let guard = ProcGuard::new(child, plan);
+        assert!(guard.terminate().expect("Termination failed").is_some());
+        assert!(called.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_custom_step_deadline() {
+        let plan = TerminationPlan::new().step_timeout(
+            |_: u32, deadline: Option<Instant>| {
+                let deadline = deadline.expect("No deadline");
+                assert!(deadline > Instant::now());
+                assert!(deadline <= Instant::now() + Duration::from_secs(2));
+                Ok(())
+            },
+            Duration::from_secs(2),
+        );
+
+        let mut child = utilities::sleep_child("3");
+        assert!(terminate(&mut child, plan)
+            .expect("Termination failed")
+            .is_none());
+        child.kill().expect("Kill failed");
+    }
+
+    #[test]
+    fn test_custom_step_timeout_escalates() {
+        let plan = TerminationPlan::new()
+            .step_timeout(
+                |_: u32, deadline: Option<Instant>| {
+                    while Instant::now() < deadline.expect("No deadline") {
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(Error::Timeout)
+                },
+                Duration::from_millis(200),
+            )
+            .kill()
+            .wait();
+
+        let child = utilities::sleep_child("5");
+        let guard = ProcGuard::new(child, plan);
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn test_custom_step_blocking_bounded() {
+        let plan = TerminationPlan::new()
+            .step_timeout(
+                |_: u32, _: Option<Instant>| {
+                    // Ignores its deadline
+                    std::thread::sleep(Duration::from_secs(30));
+                    Ok(())
+                },
+                Duration::from_millis(200),
+            )
+            .kill()
+            .wait();
+
+        let child = utilities::sleep_child("30");
+        let guard = ProcGuard::new(child, plan);
+        let start = Instant::now();
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert!(!status.success());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_custom_step_timeout_last() {
+        let plan = TerminationPlan::new().step(|_: u32, _: Option<Instant>| Err(Error::Timeout));
+
+        let mut child = utilities::sleep_child("3");
+        assert!(matches!(terminate(&mut child, plan), Err(Error::Timeout)));
+        child.kill().expect("Kill failed");
+    }
+
+    #[test]
+    fn test_custom_step_error() {
+        let plan = TerminationPlan::new()
+            .step(|_: u32, _: Option<Instant>| Err(Error::UnknownError()))
+            .kill();
+
+        let mut child = utilities::sleep_child("3");
+        assert!(matches!(
+            terminate(&mut child, plan),
+            Err(Error::UnknownError())
+        ));
+        // The plan stopped at the failing step
+        assert!(child.try_wait().expect("Wait failed").is_none());
+        child.kill().expect("Kill failed");
+    }
+
+    /// Runs a command to ask the process to stop, like `nginx -s quit`.
+    #[cfg(unix)]
+    struct RunCommand(&'static str);
+
+    #[cfg(unix)]
+    impl TerminationStep for RunCommand {
+        fn run(&self, pid: u32, _deadline: Option<Instant>) -> Result<(), Error> {
+            let status = Command::new("sh")
+                .args(["-c", self.0, "sh", &pid.to_string()])
+                .status()?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(Error::UnknownError())
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_custom_step_trait() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let plan = TerminationPlan::new()
+            .step(RunCommand("kill -TERM $1"))
+            .wait_timeout(Duration::from_secs(5))
+            .kill()
+            .wait();
+
+        let child = utilities::sleep_child("10");
+        let guard = ProcGuard::new(child, plan);
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+}
@@ -3,7 +3,6 @@ mod tests {
     use proc_guard::{terminate, ProcGuard, ProcessTermination};
     use std::thread;
     use std::time::Duration;
-    use utilities;
 
     use child_wait_timeout::ChildWT;
 
@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod tests {
+    use proc_guard::{
+        parse_env, Error, OutputRouter, ProcessTermination, Procfile, ProcfileEntry, ProcfileRunner,
+    };
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// A sink whose content can be read while the router owns it.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).expect("Invalid UTF-8")
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_procfile() {
+        let procfile = Procfile::parse(
+            "# processes\nweb: bundle exec rails server -p $PORT\n\n  worker:bundle exec sidekiq  \n",
+        )
+        .expect("Failed to parse");
+
+        assert_eq!(
+            procfile.entries,
+            [
+                ProcfileEntry {
+                    name: "web".to_string(),
+                    command: "bundle exec rails server -p $PORT".to_string(),
+                },
+                ProcfileEntry {
+                    name: "worker".to_string(),
+                    command: "bundle exec sidekiq".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_procfile_invalid_lines() {
+        for (content, line) in [
+            ("web: serve\nno command here\n", 2),
+            ("web:\n", 1),
+            ("web server: serve\n", 1),
+            ("web: serve\nweb: serve again\n", 2),
+        ] {
+            match Procfile::parse(content) {
+                Err(Error::InvalidLine(number, _)) => assert_eq!(number, line, "{:?}", content),
+                other => panic!("Unexpected result for {:?}: {:?}", content, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let vars = parse_env(
+            "# settings\nexport PORT=5000\nNAME='single # quoted'\nGREETING=\"hello \\\"world\\\"\\nbye\"\nEMPTY=\nLEVEL=debug # inline\n",
+        )
+        .expect("Failed to parse");
+
+        assert_eq!(
+            vars,
+            [
+                ("PORT".to_string(), "5000".to_string()),
+                ("NAME".to_string(), "single # quoted".to_string()),
+                ("GREETING".to_string(), "hello \"world\"\nbye".to_string()),
+                ("EMPTY".to_string(), String::new()),
+                ("LEVEL".to_string(), "debug".to_string()),
+            ]
+        );
+
+        assert!(matches!(
+            parse_env("PORT=5000\nNAME=\"unterminated\n"),
+            Err(Error::InvalidLine(2, _))
+        ));
+        assert!(matches!(
+            parse_env("not an assignment\n"),
+            Err(Error::InvalidLine(1, _))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_exit_code_of_first_exited() {
+        let sink = SharedBuffer::default();
+        let procfile =
+            Procfile::parse("server: exec sleep 30\nfailing: echo $GREETING; sleep 0.2; exit 3\n")
+                .expect("Failed to parse");
+
+        let start = Instant::now();
+        let outcome = ProcfileRunner::new(procfile)
+            .env([("GREETING".to_string(), "hello".to_string())])
+            .termination(ProcessTermination::CtrlCWaitTimeoutKill(
+                Duration::from_secs(5),
+            ))
+            .router(OutputRouter::new(sink.clone()))
+            .run()
+            .expect("Failed to run");
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(outcome.exit_code, 3);
+        assert_eq!(outcome.trigger.as_deref(), Some("failing"));
+        assert_eq!(outcome.exits.len(), 2);
+        assert_eq!(outcome.exits[0].name, "server");
+        let server = outcome.exits[0].exit.expect("No exit for the server");
+        assert!(server.ended_by_guard());
+        assert!(sink.text().contains("failing | hello\n"));
+
+        // The guard reaped the failing process itself
+        let failing = &outcome.exits[1];
+        assert!(failing.error.is_none());
+        assert!(failing.usage.is_some());
+        assert!(failing.lifetime.expect("No lifetime") >= Duration::from_millis(200));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_failure_after_clean_exit() {
+        let procfile =
+            Procfile::parse("done: exit 0\nfailing: sleep 0.5; exit 4\n").expect("Failed to parse");
+
+        let outcome = ProcfileRunner::new(procfile)
+            .termination(ProcessTermination::WaitTimeoutKill(Duration::from_secs(5)))
+            .router(OutputRouter::new(io::sink()))
+            .run()
+            .expect("Failed to run");
+
+        assert_eq!(outcome.trigger.as_deref(), Some("done"));
+        assert_eq!(outcome.exit_code, 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_clean_exit_shuts_down() {
+        let procfile =
+            Procfile::parse("server: exec sleep 30\ndone: exit 0\n").expect("Failed to parse");
+
+        let outcome = ProcfileRunner::new(procfile)
+            .termination(ProcessTermination::KillWait)
+            .router(OutputRouter::new(io::sink()))
+            .run()
+            .expect("Failed to run");
+
+        assert_eq!(outcome.trigger.as_deref(), Some("done"));
+        assert!(outcome.exits[0]
+            .exit
+            .is_some_and(|exit| exit.ended_by_guard()));
+        assert_eq!(outcome.exit_code, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_signaled_exit_code() {
+        let procfile = Procfile::parse("crashing: kill -TERM $$\n").expect("Failed to parse");
+
+        let outcome = ProcfileRunner::new(procfile)
+            .router(OutputRouter::new(io::sink()))
+            .run()
+            .expect("Failed to run");
+
+        assert_eq!(outcome.exit_code, 128 + libc::SIGTERM);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_shutdown_handle() {
+        let procfile = Procfile::parse("first: exec sleep 30\nsecond: exec sleep 30\n")
+            .expect("Failed to parse");
+        let runner = ProcfileRunner::new(procfile)
+            .termination(ProcessTermination::KillWait)
+            .router(OutputRouter::new(io::sink()));
+        let handle = runner.shutdown_handle();
+
+        let start = Instant::now();
+        let shutdown = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            handle.shutdown();
+        });
+        let outcome = runner.run().expect("Failed to run");
+        shutdown.join().unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(outcome.exit_code, 0);
+        assert_eq!(outcome.trigger, None);
+        assert!(outcome
+            .exits
+            .iter()
+            .all(|exit| exit.exit.is_some_and(|exit| exit.ended_by_guard())));
+    }
+
+    #[test]
+    fn test_env_file() {
+        let path = std::env::temp_dir().join(format!("proc_guard_env_{}", std::process::id()));
+        std::fs::write(&path, "TARGET=world\n").expect("Failed to write");
+        let sink = SharedBuffer::default();
+
+        let command = if cfg!(target_os = "windows") {
+            "main: echo hello %TARGET%"
+        } else {
+            "main: echo hello $TARGET"
+        };
+        let outcome = ProcfileRunner::new(Procfile::parse(command).expect("Failed to parse"))
+            .env_file(&path)
+            .expect("Failed to read the env file")
+            .router(OutputRouter::new(sink.clone()))
+            .run()
+            .expect("Failed to run");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(outcome.exit_code, 0);
+        assert_eq!(outcome.trigger.as_deref(), Some("main"));
+        assert!(sink.text().contains("main | hello world"));
+    }
+}
@@ -0,0 +1,114 @@
+#![cfg(unix)]
+
+#[cfg(test)]
+mod tests {
+    use proc_guard::{ProcGuard, ProcessTermination, SpawnOptions};
+    use std::io::{Read, Write};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Reads the master side of a pseudo-terminal until `expected` is read or the terminal is closed.
+    fn read_until(mut master: &std::fs::File, expected: &str) -> String {
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while !String::from_utf8_lossy(&output).contains(expected) {
+            match master.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => output.extend_from_slice(&buffer[..read]),
+            }
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    #[test]
+    fn test_pty_is_terminal() {
+        let guard = ProcGuard::spawn_with(
+            Command::new("tty").arg("-s"),
+            ProcessTermination::Wait,
+            SpawnOptions::new().pty(true),
+        )
+        .expect("Failed to start process");
+
+        assert!(guard.pty_master().is_some());
+        assert!(guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+    }
+
+    #[test]
+    fn test_pty_session_leader() {
+        let guard = ProcGuard::spawn_with(
+            &mut utilities::sleep_command("5"),
+            ProcessTermination::KillWait,
+            SpawnOptions::new().pty(true),
+        )
+        .expect("Failed to start process");
+
+        let pid = guard.child().id() as libc::pid_t;
+        assert_eq!(unsafe { libc::getsid(pid) }, pid);
+    }
+
+    #[test]
+    fn test_pty_master_read_write() {
+        let guard = ProcGuard::spawn_with(
+            &mut utilities::shell_command("read line; echo \"got $line\""),
+            ProcessTermination::WaitTimeoutKill(Duration::from_secs(5)),
+            SpawnOptions::new().pty(true),
+        )
+        .expect("Failed to start process");
+
+        let mut master = guard.pty_master().expect("No pseudo-terminal");
+        master.write_all(b"hello\n").expect("Write failed");
+        assert!(read_until(master, "got hello").contains("got hello"));
+
+        assert!(guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+    }
+
+    #[test]
+    fn test_pty_ctrl_c_process_group() {
+        // A shell waiting for a foreground job only exits once the job exits, and SIGINT sent to the shell alone does not stop the job
+        let guard = ProcGuard::spawn_with(
+            &mut utilities::shell_command("echo ready; sleep 10; echo done"),
+            ProcessTermination::CtrlCWaitTimeout(Duration::from_secs(3)),
+            SpawnOptions::new().pty(true),
+        )
+        .expect("Failed to start process");
+
+        let master = guard.pty_master().expect("No pseudo-terminal");
+        assert!(read_until(master, "ready").contains("ready"));
+        thread::sleep(Duration::from_millis(200));
+
+        let start = Instant::now();
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert!(start.elapsed() < Duration::from_secs(3));
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+    }
+
+    #[test]
+    fn test_pty_send_ctrl_c() {
+        let mut guard = ProcGuard::spawn_with(
+            &mut utilities::sleep_command("10"),
+            ProcessTermination::Wait,
+            SpawnOptions::new().pty(true),
+        )
+        .expect("Failed to start process");
+
+        guard.send_ctrl_c().expect("Failed to send Ctrl+C");
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert_eq!(status.signal(), Some(libc::SIGINT));
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(unix)]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{ProcGuard, ProcessTermination, Rlimit, Rlimits, SpawnOptions};
+    use std::process::Command;
+    use std::time::Duration;
+    #[cfg(target_os = "linux")]
+    use std::{io::Read, process::Stdio};
+
+    /// Runs `command` with `rlimits` until it exits.
+    fn run_limited(command: &mut Command, rlimits: Rlimits) -> proc_guard::ExitInfo {
+        let guard = ProcGuard::spawn_with(
+            command,
+            ProcessTermination::WaitTimeoutKill(Duration::from_secs(10)),
+            SpawnOptions::new().rlimits(rlimits),
+        )
+        .expect("Failed to start process");
+        guard
+            .supervise()
+            .expect("Supervision failed")
+            .exit
+            .expect("No exit info")
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_limits_applied() {
+        let output = Command::new("cat")
+            .arg("/proc/self/limits")
+            .output()
+            .expect("Failed to read the limits");
+        let unlimited = String::from_utf8_lossy(&output.stdout).into_owned();
+
+        let mut command = Command::new("cat");
+        command.arg("/proc/self/limits").stdout(Stdio::piped());
+        let rlimits = Rlimits::new()
+            .address_space(1 << 30)
+            .cpu_time(Duration::from_millis(1500))
+            .open_files(64)
+            .core_size(0)
+            .processes(1000)
+            .file_size(1 << 20);
+        let mut guard = ProcGuard::spawn_with(
+            &mut command,
+            ProcessTermination::Wait,
+            SpawnOptions::new().rlimits(rlimits),
+        )
+        .expect("Failed to start process");
+        let mut limits = String::new();
+        guard
+            .mut_child()
+            .stdout
+            .take()
+            .expect("No stdout")
+            .read_to_string(&mut limits)
+            .expect("Failed to read the limits");
+        guard.terminate().expect("Termination failed");
+
+        for (name, soft, hard) in [
+            ("Max address space", "1073741824", "1073741824"),
+            ("Max cpu time", "2", "3"),
+            ("Max open files", "64", "64"),
+            ("Max core file size", "0", "0"),
+            ("Max processes", "1000", "1000"),
+            ("Max file size", "1048576", "1048576"),
+        ] {
+            let line = limits
+                .lines()
+                .find(|line| line.starts_with(name))
+                .expect("Missing limit");
+            let fields: Vec<&str> = line[name.len()..].split_whitespace().collect();
+            assert_eq!((fields[0], fields[1]), (soft, hard), "{}", name);
+        }
+        assert_ne!(limits, unlimited);
+    }
+
+    #[test]
+    fn test_cpu_time_exceeded() {
+        let exit = run_limited(
+            &mut utilities::shell_command("while :; do :; done"),
+            Rlimits::new().cpu_time(Duration::from_secs(1)).core_size(0),
+        );
+        assert_eq!(exit.exceeded_rlimit(), Some(Rlimit::CpuTime));
+        assert_eq!(
+            exit.to_string(),
+            "killed by SIGXCPU, CPU time limit exceeded"
+        );
+    }
+
+    #[test]
+    fn test_file_size_exceeded() {
+        let path = std::env::temp_dir().join(format!("proc_guard_rlimit_{}", std::process::id()));
+        let mut command = Command::new("head");
+        command
+            .args(["-c", "65536", "/dev/zero"])
+            .stdout(std::fs::File::create(&path).expect("Failed to create the file"));
+
+        let exit = run_limited(&mut command, Rlimits::new().file_size(4096).core_size(0));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(exit.exceeded_rlimit(), Some(Rlimit::FileSize));
+    }
+
+    #[test]
+    fn test_open_files_exceeded() {
+        // Opening a file needs a fourth file descriptor
+        let mut command = Command::new("cat");
+        command.arg("/dev/null").stderr(std::process::Stdio::null());
+        let exit = run_limited(&mut command, Rlimits::new().open_files(3));
+        assert!(!exit.success());
+        assert_eq!(exit.exceeded_rlimit(), None);
+    }
+}
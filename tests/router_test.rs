@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod tests {
+    use proc_guard::{OutputMode, OutputRouter, ProcGuard, ProcessTermination};
+    use std::io::{self, Write};
+    use std::process::Stdio;
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// A sink whose content can be read while the router owns it.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).expect("Invalid UTF-8")
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn spawn_routed(router: &OutputRouter, name: &str, script: &str) -> ProcGuard {
+        let mut command = utilities::shell_command(script);
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        ProcGuard::spawn(&mut command, ProcessTermination::Wait)
+            .expect("Failed to start process")
+            .with_output_router(router, name)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_route_prefixes() {
+        let sink = SharedBuffer::default();
+        let router = OutputRouter::new(sink.clone());
+
+        // The prefixes are padded to the longest name registered so far
+        let worker = spawn_routed(&router, "worker", "printf 'job 1\\njob 2'");
+        let web = spawn_routed(&router, "web", "echo listening; echo warning >&2");
+        web.terminate().expect("Termination failed");
+        worker.terminate().expect("Termination failed");
+        router.flush();
+
+        let text = sink.text();
+        let mut lines: Vec<&str> = text.lines().collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            [
+                "web    | listening",
+                "web    | warning",
+                "worker | job 1",
+                "worker | job 2"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_route_order_per_process() {
+        let sink = SharedBuffer::default();
+        let router = OutputRouter::new(sink.clone());
+
+        let guard = spawn_routed(&router, "seq", "for i in 1 2 3 4 5; do echo $i; done");
+        guard.terminate().expect("Termination failed");
+        router.flush();
+
+        assert_eq!(sink.text(), "seq | 1\nseq | 2\nseq | 3\nseq | 4\nseq | 5\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_route_colors_timestamps() {
+        let sink = SharedBuffer::default();
+        let router = OutputRouter::new(sink.clone())
+            .colors(true)
+            .timestamps(true);
+
+        let first = spawn_routed(&router, "a", "echo one");
+        first.terminate().expect("Termination failed");
+        let second = spawn_routed(&router, "b", "echo two");
+        second.terminate().expect("Termination failed");
+        router.flush();
+
+        let text = sink.text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (line, (color, suffix)) in lines.iter().zip([
+            ("\x1b[36m", "a |\x1b[0m one"),
+            ("\x1b[33m", "b |\x1b[0m two"),
+        ]) {
+            let rest = line.strip_prefix(color).expect("No colour");
+            let (time, rest) = rest.split_once(' ').expect("No timestamp");
+            assert_eq!(time.len(), "HH:MM:SS.mmm".len());
+            assert_eq!(&time[2..3], ":");
+            assert_eq!(&time[8..9], ".");
+            assert_eq!(rest, suffix);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_route_blocked_sink_back_pressure() {
+        /// A sink blocking every write until it is opened.
+        #[derive(Clone, Default)]
+        struct GatedSink(Arc<(Mutex<bool>, Condvar)>);
+
+        impl GatedSink {
+            fn open(&self) {
+                *self.0 .0.lock().unwrap() = true;
+                self.0 .1.notify_all();
+            }
+        }
+
+        impl Write for GatedSink {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let (open, changed) = &*self.0;
+                let _open = changed
+                    .wait_while(open.lock().unwrap(), |open| !*open)
+                    .unwrap();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let sink = GatedSink::default();
+        let router = OutputRouter::new(sink.clone());
+        // 64 MiB of output, far more than the router queues
+        let mut guard = spawn_routed(
+            &router,
+            "noisy",
+            "head -c 67108864 /dev/zero | tr '\\0' y | fold -w 1023",
+        );
+
+        // The process is not drained while the sink is blocked
+        thread::sleep(Duration::from_secs(1));
+        assert!(guard.mut_child().try_wait().expect("Wait failed").is_none());
+
+        sink.open();
+        let start = Instant::now();
+        let status = guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status");
+        assert!(status.success());
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_route_after_with_output() {
+        let sink = SharedBuffer::default();
+        let router = OutputRouter::new(sink.clone());
+        let mut command = utilities::shell_command("sleep 0.2; echo routed");
+        command.stdout(Stdio::piped());
+
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::Wait)
+            .expect("Failed to start process")
+            .with_output(OutputMode::Capture)
+            .with_output_router(&router, "late");
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        router.flush();
+
+        assert_eq!(sink.text(), "late | routed\n");
+        assert_eq!(
+            outcome.output.expect("No captured output").stdout,
+            b"routed\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_route_replaced_ends_previous() {
+        let first = SharedBuffer::default();
+        let second = SharedBuffer::default();
+        let first_router = OutputRouter::new(first.clone());
+        let second_router = OutputRouter::new(second.clone());
+
+        let guard = spawn_routed(
+            &first_router,
+            "first",
+            "printf partial; sleep 0.5; echo; echo next",
+        );
+        thread::sleep(Duration::from_millis(200));
+        let guard = guard.with_output_router(&second_router, "second");
+        guard.terminate().expect("Termination failed");
+        first_router.flush();
+        second_router.flush();
+
+        assert_eq!(first.text(), "first | partial\n");
+        assert_eq!(second.text(), "second | \nsecond | next\n");
+    }
+}
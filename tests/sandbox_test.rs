@@ -0,0 +1,130 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{
+        ExitInfo, PathAccess, ProcGuard, ProcessTermination, Sandbox, SeccompAction, SeccompPreset,
+        SpawnOptions,
+    };
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    /// Runs `command` in `sandbox` until it exits, along with its stdout.
+    fn run_sandboxed(
+        command: &mut Command,
+        sandbox: Sandbox,
+    ) -> std::io::Result<(ExitInfo, String)> {
+        command.stdout(Stdio::piped());
+        let mut guard = ProcGuard::spawn_with(
+            command,
+            ProcessTermination::KillWait,
+            SpawnOptions::new().sandbox(sandbox),
+        )?;
+        let mut output = String::new();
+        guard
+            .mut_child()
+            .stdout
+            .take()
+            .expect("No stdout")
+            .read_to_string(&mut output)
+            .expect("Failed to read the output");
+        let exit = guard
+            .supervise()
+            .expect("Supervision failed")
+            .exit
+            .expect("No exit info");
+        Ok((exit, output))
+    }
+
+    #[test]
+    fn test_no_new_privs() {
+        let (exit, output) = run_sandboxed(
+            &mut utilities::shell_command("grep NoNewPrivs /proc/self/status"),
+            Sandbox::new(),
+        )
+        .expect("Failed to start process");
+
+        assert!(exit.success());
+        assert_eq!(
+            output.split_whitespace().collect::<Vec<_>>(),
+            ["NoNewPrivs:", "1"]
+        );
+        assert!(!exit.violated_seccomp());
+    }
+
+    #[test]
+    fn test_no_fork_violation() {
+        let (exit, output) = run_sandboxed(
+            &mut utilities::shell_command("echo before; /bin/true; echo after"),
+            Sandbox::new().seccomp(SeccompPreset::NoFork),
+        )
+        .expect("Failed to start process");
+
+        assert_eq!(output, "before\n");
+        assert!(exit.violated_seccomp());
+        assert!(exit.to_string().ends_with("seccomp violation"), "{}", exit);
+    }
+
+    #[test]
+    fn test_custom_syscalls_with_errno() {
+        let (exit, output) = run_sandboxed(
+            &mut utilities::shell_command("exec uname 2>/dev/null"),
+            Sandbox::new()
+                .deny_syscalls([libc::SYS_uname])
+                .seccomp_action(SeccompAction::Errno(libc::EPERM)),
+        )
+        .expect("Failed to start process");
+
+        assert!(!exit.success());
+        assert!(!exit.violated_seccomp());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_no_network_violation() {
+        let mut command = Command::new("curl");
+        command.args(["--silent", "http://127.0.0.1:9/"]);
+        let (exit, _) = match run_sandboxed(
+            &mut command,
+            Sandbox::new().seccomp(SeccompPreset::NoNetwork),
+        ) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Skipped, curl is not available: {}", e);
+                return;
+            }
+        };
+
+        assert!(exit.violated_seccomp());
+    }
+
+    #[test]
+    fn test_landlock_restricts_paths() {
+        let dir = std::env::temp_dir().join(format!("proc_guard_sandbox_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("Failed to create the directory");
+        let mut sandbox = Sandbox::new().allow_path(&dir, PathAccess::ReadWrite);
+        for path in ["/bin", "/usr", "/lib", "/lib64", "/etc"] {
+            if std::path::Path::new(path).exists() {
+                sandbox = sandbox.allow_path(path, PathAccess::ReadOnly);
+            }
+        }
+
+        let script = format!(
+            "echo allowed > {0}/file && cat {0}/file; cat {1} 2>/dev/null || echo denied; (echo x > /proc/self/comm) 2>/dev/null || echo denied",
+            dir.display(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+        );
+        let result = run_sandboxed(&mut utilities::shell_command(&script), sandbox);
+        std::fs::remove_dir_all(&dir).expect("Failed to remove the directory");
+        let (exit, output) = match result {
+            Ok(result) => result,
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+                eprintln!("Skipped, Landlock is not available");
+                return;
+            }
+            Err(e) => panic!("Failed to start process: {}", e),
+        };
+
+        assert!(exit.success());
+        assert_eq!(output, "allowed\ndenied\ndenied\n");
+    }
+}
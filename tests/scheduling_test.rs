@@ -0,0 +1,50 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{IoPriority, ProcGuard, ProcessTermination, Scheduling, SpawnOptions};
+
+    fn spawn_scheduled(scheduling: Scheduling) -> std::io::Result<ProcGuard> {
+        ProcGuard::spawn_with(
+            &mut utilities::sleep_command("30"),
+            ProcessTermination::KillWait,
+            SpawnOptions::new().scheduling(scheduling),
+        )
+    }
+
+    #[test]
+    fn test_scheduling_applied() {
+        let guard = spawn_scheduled(
+            Scheduling::new()
+                .nice(7)
+                .cpu_affinity([0])
+                .oom_score_adj(600)
+                .io_priority(IoPriority::BestEffort(6)),
+        )
+        .expect("Failed to start process");
+
+        let scheduling = guard.scheduling().expect("Failed to read the scheduling");
+        assert_eq!(scheduling.nice, 7);
+        assert_eq!(scheduling.cpu_affinity, vec![0]);
+        assert_eq!(scheduling.oom_score_adj, 600);
+        assert_eq!(scheduling.io_priority, Some(IoPriority::BestEffort(6)));
+    }
+
+    #[test]
+    fn test_scheduling_inherited() {
+        let guard = spawn_scheduled(Scheduling::new().io_priority(IoPriority::Idle))
+            .expect("Failed to start process");
+
+        let scheduling = guard.scheduling().expect("Failed to read the scheduling");
+        let current = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        assert_eq!(scheduling.nice, current);
+        assert_eq!(scheduling.io_priority, Some(IoPriority::Idle));
+        assert!(!scheduling.cpu_affinity.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_cpu() {
+        let error = spawn_scheduled(Scheduling::new().cpu_affinity([1 << 20]))
+            .expect_err("The CPU does not exist");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -5,7 +5,6 @@ mod tests {
     use proc_guard::send_ctrl_c;
     use std::thread;
     use std::time::Duration;
-    use utilities;
 
     #[test]
     fn test_send_ctrl_c_unix() {
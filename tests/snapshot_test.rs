@@ -0,0 +1,104 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{KillSnapshot, ProcGuard, ProcSnapshot, ProcessTermination};
+    use std::process::Command;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_capture_current_process() {
+        let snapshot = ProcSnapshot::capture(std::process::id());
+        let process = &snapshot.processes[0];
+        assert_eq!(process.pid, std::process::id());
+        assert!(!process.cmdline.is_empty());
+        assert!(process.status.contains(&format!("Pid:\t{}", process.pid)));
+        assert!(process.fds.iter().any(|fd| fd.fd == 0));
+        assert!(process
+            .threads
+            .iter()
+            .any(|thread| thread.tid == process.pid));
+        assert!(snapshot.path.is_none());
+    }
+
+    #[test]
+    fn test_capture_missing_process() {
+        let mut child = Command::new("true")
+            .spawn()
+            .expect("Failed to start process");
+        let pid = child.id();
+        child.wait().expect("Wait failed");
+
+        assert!(ProcSnapshot::capture(pid).processes.is_empty());
+    }
+
+    #[test]
+    fn test_kill_snapshot_descendants() {
+        let mut command = utilities::shell_command("sleep 10 & sleep 10 & wait");
+        let guard = ProcGuard::spawn(
+            &mut command,
+            ProcessTermination::WaitTimeoutKill(Duration::from_millis(300)),
+        )
+        .expect("Failed to start process")
+        .with_kill_snapshot(KillSnapshot::new());
+        let pid = guard.child().id();
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert_eq!(outcome.snapshots.len(), 1);
+        let processes = &outcome.snapshots[0].processes;
+        for process in &processes[1..] {
+            unsafe { libc::kill(process.pid as libc::pid_t, libc::SIGKILL) };
+        }
+
+        assert_eq!(processes.len(), 3);
+        assert_eq!(processes[0].pid, pid);
+        assert_eq!(processes[0].cmdline[0], "sh");
+        assert!(processes[0].wchan.is_some());
+        assert!(processes[1..]
+            .iter()
+            .all(|process| process.cmdline == ["sleep", "10"]));
+    }
+
+    #[test]
+    fn test_kill_snapshot_directory() {
+        let directory =
+            std::env::temp_dir().join(format!("proc_guard_snapshot_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).expect("Failed to create directory");
+
+        let guard = ProcGuard::new(utilities::sleep_child("5"), ProcessTermination::KillWait)
+            .with_kill_snapshot(KillSnapshot::new().directory(&directory));
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+
+        let path = outcome.snapshots[0]
+            .path
+            .clone()
+            .expect("Snapshot not written");
+        let report = std::fs::read_to_string(&path).expect("Failed to read snapshot");
+        std::fs::remove_dir_all(&directory).expect("Failed to remove directory");
+        assert!(path.starts_with(&directory));
+        assert!(report.starts_with("=== process"));
+        assert!(report.contains("--- threads"));
+        assert_eq!(report, outcome.snapshots[0].to_string());
+    }
+
+    #[test]
+    fn test_kill_snapshot_exited() {
+        let child = Command::new("true")
+            .spawn()
+            .expect("Failed to start process");
+        thread::sleep(Duration::from_millis(200));
+
+        let guard = ProcGuard::new(child, ProcessTermination::KillWait)
+            .with_kill_snapshot(KillSnapshot::new());
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert!(outcome.status.expect("No exit status").success());
+        assert!(outcome.snapshots.is_empty());
+    }
+
+    #[test]
+    fn test_no_kill_snapshot() {
+        let guard = ProcGuard::new(utilities::sleep_child("5"), ProcessTermination::KillWait);
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert!(outcome.snapshots.is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use proc_guard::{terminate, Error, ProcGuard, ProcessTermination, SpawnOptions};
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+
+    #[test]
+    fn test_close_stdin_not_piped() {
+        let child = utilities::sleep_child("3");
+        let guard = ProcGuard::new(child, ProcessTermination::CloseStdinWait);
+        assert!(matches!(guard.terminate(), Err(Error::StdinNotPiped)));
+    }
+
+    #[test]
+    fn test_write_stdin_not_piped() {
+        let mut child = utilities::sleep_child("3");
+        let result = terminate(&mut child, ProcessTermination::WriteStdin(b"quit\n"));
+        assert!(matches!(result, Err(Error::StdinNotPiped)));
+        child.kill().expect("Kill failed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_close_stdin_wait() {
+        let mut command = Command::new("cat");
+        command.stdin(Stdio::piped()).stdout(Stdio::null());
+        let guard = ProcGuard::spawn(&mut command, ProcessTermination::CloseStdinWait)
+            .expect("Failed to start process");
+        assert!(guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_close_stdin_wait_timeout_kill_timeout() {
+        let mut command = utilities::sleep_command("5");
+        command.stdin(Stdio::piped());
+        let guard = ProcGuard::spawn(
+            &mut command,
+            ProcessTermination::CloseStdinWaitTimeoutKill(Duration::from_secs(1)),
+        )
+        .expect("Failed to start process");
+        assert!(guard.terminate().expect("Termination failed").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_stdin_wait_timeout() {
+        let mut command = utilities::shell_command("read command; test \"$command\" = quit");
+        command.stdin(Stdio::piped());
+        let guard = ProcGuard::spawn(
+            &mut command,
+            ProcessTermination::WriteStdinWaitTimeout(b"quit\n", Duration::from_secs(5)),
+        )
+        .expect("Failed to start process");
+        assert!(guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_stdin_wait_timeout_timeout() {
+        let mut command = utilities::shell_command("read command; exec sleep 5");
+        command.stdin(Stdio::piped());
+        let mut child = command.spawn().expect("Failed to start process");
+        let result = terminate(
+            &mut child,
+            ProcessTermination::WriteStdinWaitTimeout(b"quit\n", Duration::from_secs(1)),
+        );
+        assert!(result.is_err());
+        child.kill().expect("Kill failed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_stdin_exited() {
+        let mut command = utilities::shell_command("exit 0");
+        command.stdin(Stdio::piped());
+        let mut child = command.spawn().expect("Failed to start process");
+        child.wait().expect("Wait failed");
+
+        // Writing to a process that exited is not an error, the wait reports its status
+        let result = terminate(&mut child, ProcessTermination::WriteStdinWait(b"quit\n"));
+        assert!(result.expect("Termination failed").is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_close_stdin_pty() {
+        let guard = ProcGuard::spawn_with(
+            &mut Command::new("cat"),
+            ProcessTermination::CloseStdinWaitTimeout(Duration::from_secs(5)),
+            SpawnOptions::new().pty(true),
+        )
+        .expect("Failed to start process");
+        assert!(guard
+            .terminate()
+            .expect("Termination failed")
+            .expect("No exit status")
+            .success());
+    }
+}
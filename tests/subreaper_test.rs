@@ -0,0 +1,102 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{ProcGuard, ProcessTermination, SpawnOptions, Subreaper};
+    use std::os::unix::process::ExitStatusExt;
+    use std::path::Path;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+    use std::thread;
+    use std::time::Duration;
+
+    /// The subreaper state belongs to the whole process, the tests take turns.
+    static SUBREAPER: Mutex<()> = Mutex::new(());
+
+    fn serialize() -> MutexGuard<'static, ()> {
+        SUBREAPER.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_subreaper() -> bool {
+        let mut subreaper: libc::c_int = 0;
+        assert_eq!(
+            unsafe { libc::prctl(libc::PR_GET_CHILD_SUBREAPER, &mut subreaper) },
+            0
+        );
+        subreaper != 0
+    }
+
+    #[test]
+    fn test_enable_restores_state() {
+        let _subreaper = serialize();
+        assert!(!is_subreaper());
+
+        let subreaper = Subreaper::enable().expect("Failed to become a subreaper");
+        assert!(is_subreaper());
+
+        drop(subreaper);
+        assert!(!is_subreaper());
+    }
+
+    #[test]
+    fn test_kill_orphan() {
+        let _subreaper = serialize();
+        let subreaper = Subreaper::enable().expect("Failed to become a subreaper");
+
+        // The subshell exits right away, orphaning the background sleep
+        let mut command = utilities::shell_command("(sleep 30 &); exec sleep 30");
+        let guard = ProcGuard::spawn_with(
+            &mut command,
+            ProcessTermination::KillWait,
+            SpawnOptions::new().subreaper(&subreaper),
+        )
+        .expect("Failed to start process");
+        thread::sleep(Duration::from_millis(600));
+
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert_eq!(outcome.orphans.len(), 1);
+        let orphan = outcome.orphans[0];
+        assert_eq!(orphan.status.signal(), Some(libc::SIGKILL));
+        assert!(!Path::new(&format!("/proc/{}", orphan.pid)).exists());
+    }
+
+    #[test]
+    fn test_reap_exited_orphan() {
+        let _subreaper = serialize();
+        let subreaper = Subreaper::enable().expect("Failed to become a subreaper");
+
+        let mut command = utilities::shell_command("(sleep 0.1 &); sleep 1");
+        let guard = ProcGuard::spawn_with(
+            &mut command,
+            ProcessTermination::KillWait,
+            SpawnOptions::new().subreaper(&subreaper),
+        )
+        .expect("Failed to start process");
+
+        let outcome = guard.supervise().expect("Supervision failed");
+        assert_eq!(outcome.orphans.len(), 1);
+        assert!(outcome.orphans[0].status.success());
+    }
+
+    #[test]
+    fn test_orphans_linked_to_their_guard() {
+        let _subreaper = serialize();
+        let subreaper = Subreaper::enable().expect("Failed to become a subreaper");
+
+        let mut orphaning = utilities::shell_command("(sleep 30 &); exec sleep 30");
+        let orphaning = ProcGuard::spawn_with(
+            &mut orphaning,
+            ProcessTermination::KillWait,
+            SpawnOptions::new().subreaper(&subreaper),
+        )
+        .expect("Failed to start process");
+        let other = ProcGuard::new(utilities::sleep_child("30"), ProcessTermination::KillWait)
+            .with_subreaper(&subreaper);
+        thread::sleep(Duration::from_millis(600));
+
+        let outcome = other.terminate_with_output().expect("Termination failed");
+        assert!(outcome.orphans.is_empty());
+        let outcome = orphaning
+            .terminate_with_output()
+            .expect("Termination failed");
+        assert_eq!(outcome.orphans.len(), 1);
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{Error, ProcGuard, ProcessTermination, TerminationPlan, TreeOrder};
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Spawns `script`, which prints the pid of a descendant first.
+    fn spawn_tree(script: &str, order: TreeOrder, plan: TerminationPlan) -> (ProcGuard, u32) {
+        let mut command = utilities::shell_command(script);
+        command.stdout(Stdio::piped());
+        let mut guard = ProcGuard::spawn(&mut command, plan)
+            .expect("Failed to start process")
+            .with_tree_kill(order);
+
+        let stdout = guard.mut_child().stdout.take().expect("No stdout");
+        let mut line = String::new();
+        BufReader::new(stdout)
+            .read_line(&mut line)
+            .expect("Failed to read the pid");
+        (guard, line.trim().parse().expect("Invalid pid"))
+    }
+
+    /// Returns whether a process is running, a zombie having exited.
+    fn is_running(pid: u32) -> bool {
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => !stat
+                .rsplit_once(") ")
+                .is_some_and(|(_, fields)| fields.starts_with('Z')),
+            Err(_) => false,
+        }
+    }
+
+    /// Waits for a process to exit, it is reaped by init.
+    fn exits(pid: u32) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if !is_running(pid) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn test_kill_session_leader_descendant() {
+        for order in [TreeOrder::TopDown, TreeOrder::BottomUp] {
+            let (guard, descendant) = spawn_tree(
+                "setsid sleep 30 & echo $!; exec sleep 30",
+                order,
+                ProcessTermination::KillWait.into(),
+            );
+            assert!(is_running(descendant));
+
+            guard.terminate().expect("Termination failed");
+            assert!(exits(descendant), "{:?} spared the descendant", order);
+        }
+    }
+
+    #[test]
+    fn test_ctrl_c_descendant() {
+        let (guard, descendant) = spawn_tree(
+            // Asynchronous commands ignore SIGINT, the descendant runs in the foreground of the shell
+            "sh -c 'echo $$; exec setsid sleep 30'; exit",
+            TreeOrder::BottomUp,
+            ProcessTermination::CtrlCWait.into(),
+        );
+
+        guard.terminate().expect("Termination failed");
+        assert!(exits(descendant));
+    }
+
+    #[test]
+    fn test_kill_reparented_descendant() {
+        // The shell exits on Ctrl+C, its descendant ignoring it is reparented to init before the kill
+        let plan = TerminationPlan::new()
+            .ctrl_c()
+            .step(|_: u32, _: Option<Instant>| -> Result<(), Error> {
+                thread::sleep(Duration::from_millis(300));
+                Ok(())
+            })
+            .kill()
+            .wait();
+        let (guard, descendant) = spawn_tree(
+            "setsid sh -c 'trap \"\" INT; exec sleep 30' & echo $!; wait",
+            TreeOrder::TopDown,
+            plan,
+        );
+        thread::sleep(Duration::from_millis(100));
+
+        guard.terminate().expect("Termination failed");
+        assert!(exits(descendant));
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(unix)]
+#[cfg(test)]
+mod tests {
+    use proc_guard::{ProcGuard, ProcessTermination, TerminationPlan};
+    use std::time::Duration;
+
+    /// Keeps a CPU busy for a few hundred milliseconds.
+    const BUSY_SCRIPT: &str = "i=0; while [ $i -lt 200000 ]; do i=$((i+1)); done";
+
+    #[test]
+    fn test_usage_of_exited_process() {
+        let guard = ProcGuard::spawn(
+            &mut utilities::shell_command(BUSY_SCRIPT),
+            ProcessTermination::KillWait,
+        )
+        .expect("Failed to start process");
+        let outcome = guard.supervise().expect("Supervision failed");
+
+        let usage = outcome.usage.expect("No resource usage");
+        assert!(usage.cpu_time() > Duration::ZERO);
+        assert!(usage.max_rss > 0);
+        assert!(usage.minor_faults > 0);
+        assert!(outcome.lifetime >= usage.user_time);
+    }
+
+    #[test]
+    fn test_usage_of_waited_process() {
+        let guard = ProcGuard::spawn(
+            &mut utilities::shell_command(BUSY_SCRIPT),
+            ProcessTermination::WaitTimeoutKill(Duration::from_secs(30)),
+        )
+        .expect("Failed to start process");
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+
+        assert!(outcome.status.expect("No exit status").success());
+        let usage = outcome.usage.expect("No resource usage");
+        assert!(usage.cpu_time() > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_usage_of_killed_process() {
+        let guard = ProcGuard::new(
+            utilities::sleep_child("30"),
+            ProcessTermination::WaitTimeoutKill(Duration::from_millis(300)),
+        );
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+
+        assert!(outcome.status.is_none());
+        assert!(outcome.lifetime >= Duration::from_millis(300));
+
+        let guard = ProcGuard::new(utilities::sleep_child("30"), ProcessTermination::KillWait);
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+        assert!(outcome.usage.is_some());
+    }
+
+    #[test]
+    fn test_no_usage_without_wait() {
+        let guard = ProcGuard::new(utilities::sleep_child("30"), TerminationPlan::new().kill());
+        let outcome = guard.terminate_with_output().expect("Termination failed");
+
+        assert!(outcome.status.is_none());
+        assert!(outcome.usage.is_none());
+    }
+
+    #[test]
+    fn test_lifetime_from_spawn() {
+        let guard = ProcGuard::spawn(
+            &mut utilities::shell_command("sleep 0.3"),
+            ProcessTermination::KillWait,
+        )
+        .expect("Failed to start process");
+        let outcome = guard.supervise().expect("Supervision failed");
+
+        assert!(outcome.lifetime >= Duration::from_millis(300));
+        assert!(outcome.lifetime < Duration::from_secs(5));
+    }
+}
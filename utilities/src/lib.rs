@@ -21,3 +21,19 @@ pub fn sleep_child(time: &str) -> Child {
         .spawn()
         .expect("Failed to start sleep command")
 }
+
+pub fn shell_command(script: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut command = Command::new("cmd");
+        command.args(["/C", script]);
+        command
+    }
+
+    #[cfg(unix)]
+    {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+}